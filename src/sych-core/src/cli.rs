@@ -1,16 +1,14 @@
-use std::{fs, path::Path, path::PathBuf};
+use std::io::IsTerminal;
+use std::{fs, path::Path, path::PathBuf, str::FromStr};
 
 use anyhow::{Ok, Result};
-use indexmap::IndexMap;
 use structopt::StructOpt;
+use sych::{
+    Diagnostic, Docs, MergeStrategy, Project, Severity, TextFlavor, TextStats, SYCH_SNAPSHOT,
+    SYCH_TOML,
+};
 
-use crate::transpiler::Doc;
-use crate::SychConfig;
-
-type IndexedBlockMap = IndexMap<String, Vec<markdown::Block>>;
-
-static HBS_FILE: &str = include_str!("../templates/sych.hbs");
-static SYCH_HBS_NAME: &str = "salt.hbs";
+use crate::pager::page;
 
 const SYCH_INIT_DATA: &str = r#"[meta]
 title = "sych docs"
@@ -19,8 +17,16 @@ version = "0.1.0"
 description = "sych generates docs from .md files in your project"
 "#;
 
-const SYCH_TOML: &str = "sych.toml";
 const SYCH_HTML: &str = ".sych.html";
+const SYCH_JSON: &str = ".sych.json";
+const SYCH_MD: &str = ".sych.md";
+const SYCH_TXT: &str = ".sych.txt";
+const SYCH_EPUB: &str = ".sych.epub";
+const SYCH_ATOM: &str = ".sych.atom";
+const SYCH_CACHE_DIR: &str = ".sych-cache";
+const SYCH_SITE_DIR: &str = ".sych-site";
+const SYCH_MAN_DIR: &str = ".sych-man";
+const SYCH_VERSIONS_DIR: &str = ".sych-versions";
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "sych", about = "A very fast document site generator")]
@@ -40,6 +46,109 @@ pub struct SychCLI {
 
     #[structopt(long, short, help = "generate a minified html for publishing")]
     pub release: bool,
+
+    #[structopt(subcommand)]
+    pub cmd: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Checks links, anchors and the structure of the docs without writing anything
+    Check {
+        #[structopt(long, help = "print the diagnostics as json")]
+        json: bool,
+
+        #[structopt(long, help = "fail on warnings as well as errors")]
+        strict: bool,
+
+        #[structopt(long, help = "request every http(s) link found in the docs")]
+        external: bool,
+    },
+    /// Builds the docs without opening them
+    Build {
+        #[structopt(
+            long,
+            default_value = "html",
+            help = "html: a single .sych.html file, site: one page per section, json: the whole index, markdown/text: all sections in one file, epub: an e-book, man: a man page per section"
+        )]
+        format: OutputFormat,
+
+        #[structopt(
+            long,
+            help = "output file or directory of the format",
+            value_name = "PATH"
+        )]
+        out: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            help = "also build every git tag matching [versions] tags, each into a directory of .sych-versions/ (or --out) with a switcher between them"
+        )]
+        versions: bool,
+    },
+    /// Manages the list of markdown files in sych.toml
+    Refs {
+        #[structopt(subcommand)]
+        cmd: RefsCommand,
+    },
+    /// Inspects the configuration
+    Config {
+        #[structopt(subcommand)]
+        cmd: ConfigCommand,
+    },
+    /// Shows a section in the terminal
+    Show {
+        #[structopt(help = "title or anchor of the section")]
+        section: String,
+
+        #[structopt(long, help = "print the section instead of opening it in $PAGER")]
+        no_pager: bool,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum RefsCommand {
+    /// Updates `refs` with the markdown files found inside the root
+    Sync,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum ConfigCommand {
+    /// Prints the configuration with every default filled in
+    Show {
+        #[structopt(long, help = "show where every value comes from")]
+        origin: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Html,
+    Site,
+    Json,
+    Markdown,
+    Text,
+    Epub,
+    Man,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "html" => Ok(OutputFormat::Html),
+            "site" => Ok(OutputFormat::Site),
+            "json" => Ok(OutputFormat::Json),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "text" | "txt" => Ok(OutputFormat::Text),
+            "epub" => Ok(OutputFormat::Epub),
+            "man" => Ok(OutputFormat::Man),
+            _ => Err(anyhow::Error::msg(format!(
+                "unknown format {s}, expected one of: html, site, json, markdown, text, epub, man"
+            ))),
+        }
+    }
 }
 
 impl SychCLI {
@@ -48,8 +157,7 @@ impl SychCLI {
     }
 
     pub fn execute(&self) -> Result<()> {
-        let mut root = get_cwd()?;
-        let config_path = root.join(SYCH_TOML);
+        let config_path = PathBuf::from(SYCH_TOML);
 
         // intializes sych.toml with default configurations
         // SYCH_INIT_DATA is the defualt content
@@ -60,134 +168,309 @@ impl SychCLI {
         // check if sych config is initialized in the directory
         // from which user is running the 'sych' command
         if !config_path.exists() {
-            return Err(anyhow::Error::msg("not a sych project. do 'sych -i' ..."));
+            return Err(sych::Error::Config {
+                message: "not a sych project. do 'sych -i' ...".to_owned(),
+                span: None,
+            }
+            .into());
         }
 
         // read sych.toml inside the current working directory
-        let mut sych_cfg = toml::from_str::<SychConfig>(&fs::read_to_string(&config_path)?)?;
-        // validate_config(&sych_cfg)?;
-
+        // along with the user config, sych.local.toml and SYCH_* variables
         // if user has mentioned custom root folder use that!
         // we give precedence to whatever is passed through
         // CLI
+        let mut builder = Project::builder().config_path(&config_path);
         if let Some(user_root) = self.root.as_ref() {
+            builder = builder.root(user_root);
+        }
+        let project = builder.build()?;
+        for warning in project.warnings() {
+            eprint!("{}", warning.report(stderr_color()));
+        }
+
+        if let Some(Command::Config {
+            cmd: ConfigCommand::Show { origin },
+        }) = self.cmd
+        {
+            if origin {
+                print!("{}", project.config_origins()?);
+            } else {
+                let effective = toml::Value::try_from(project.effective_config())?;
+                print!("{}", toml::to_string_pretty(&effective)?);
+            }
+            return Ok(());
+        }
+
+        // json output of `sych check` has to stay parseable and `sych show`
+        // prints nothing but the section
+        let quiet = matches!(
+            self.cmd,
+            Some(Command::Check { json: true, .. } | Command::Show { .. })
+        );
+        let root = project.root().to_path_buf();
+        let custom_root = self.root.is_some()
+            || project
+                .config()
+                .doc
+                .as_ref()
+                .is_some_and(|doc| doc.root.is_some());
+        if custom_root && !quiet {
             // TODO: some colorized output
-            root = user_root.clone();
-            println!("using root: {}", root.to_str().unwrap());
-        } else if let Some(doc_config) = sych_cfg.doc.as_ref() {
-            if let Some(config_root) = doc_config.root.as_ref() {
-                root = PathBuf::from(config_root);
-                println!("using root: {}", root.to_str().unwrap());
+            println!("using root: {}", root.display());
+        }
+
+        if let Some(Command::Refs {
+            cmd: RefsCommand::Sync,
+        }) = self.cmd
+        {
+            let change = project.sync_refs()?;
+            if change.added.is_empty() && change.removed.is_empty() {
+                println!("refs are up to date");
+                return Ok(());
+            }
+            for reference in &change.added {
+                println!("  + {reference}");
+            }
+            for reference in &change.removed {
+                println!("  - {reference}");
             }
+            println!("updated refs in {}", config_path.display());
+            return Ok(());
+        }
+
+        // index and combine the markdown file content into sections
+        let docs = project.index()?;
+
+        if let Some(Command::Check {
+            json,
+            strict,
+            external,
+        }) = self.cmd
+        {
+            let cache_dir = config_path.with_file_name(SYCH_CACHE_DIR);
+            let diagnostics = docs.check(external.then_some(cache_dir.as_path()));
+            return report_diagnostics(diagnostics, json, strict);
+        }
+
+        if let Some(Command::Show { section, no_pager }) = self.cmd.as_ref() {
+            return page(&docs.terminal(section)?, *no_pager);
         }
 
-        // start finding markdown files recursively inside "root"
-        let markdown_files: Vec<String> = rust_search::SearchBuilder::default()
-            .location(root.as_path())
-            .ext("md")
-            .build()
-            .collect();
+        report_collisions(&docs)?;
+        // `sych check` reports broken snippets, every output needs them
+        docs.check_snippets()?;
 
-        let mut docs_index: IndexedBlockMap = IndexMap::new();
-        // index and combine the markdown file content into a single
-        // Vec<> of markdown blocks
-        index_markdown_files(&sych_cfg, &markdown_files, &mut docs_index)?;
+        if let Some(Command::Build {
+            format,
+            out,
+            versions: true,
+        }) = self.cmd.as_ref()
+        {
+            let site = match format {
+                OutputFormat::Html => false,
+                OutputFormat::Site => true,
+                _ => {
+                    return Err(sych::Error::Config {
+                        message: "--versions builds the html and site formats only".to_owned(),
+                        span: None,
+                    }
+                    .into())
+                }
+            };
+            let out_dir = out.clone().unwrap_or_else(|| root.join(SYCH_VERSIONS_DIR));
+            let labels = project.write_versions(&out_dir, site, self.release)?;
+            println!(
+                "{} versions written to {}: {}",
+                labels.len(),
+                out_dir.display(),
+                labels.join(", ")
+            );
+            return Ok(());
+        }
+
+        if let Some(Command::Build { format, out, .. }) = self.cmd.as_ref() {
+            let built = match format {
+                OutputFormat::Html => {
+                    docs.write_html(&root.join(SYCH_HTML), self.release)?;
+                    save_atom(&docs, &root)
+                }
+                OutputFormat::Site => {
+                    let out_dir = out.clone().unwrap_or_else(|| root.join(SYCH_SITE_DIR));
+                    docs.write_site(&out_dir, self.release)?;
+                    if project
+                        .config()
+                        .site
+                        .as_ref()
+                        .and_then(|s| s.base_url.as_ref())
+                        .is_none()
+                    {
+                        println!("set `base_url` in [site] to get a sitemap.xml");
+                    }
+                    println!("site written to {}", out_dir.display());
+                    Ok(())
+                }
+                OutputFormat::Json => {
+                    let json_path = out.clone().unwrap_or_else(|| root.join(SYCH_JSON));
+                    fs::write(json_path, docs.json()?).map_err(anyhow::Error::from)
+                }
+                OutputFormat::Markdown => {
+                    let md_path = out.clone().unwrap_or_else(|| root.join(SYCH_MD));
+                    save_text(&docs, &md_path, TextFlavor::Markdown)
+                }
+                OutputFormat::Text => {
+                    let txt_path = out.clone().unwrap_or_else(|| root.join(SYCH_TXT));
+                    save_text(&docs, &txt_path, TextFlavor::Text)
+                }
+                OutputFormat::Epub => {
+                    let epub_path = out.clone().unwrap_or_else(|| root.join(SYCH_EPUB));
+                    docs.write_epub(fs::File::create(&epub_path)?)?;
+                    println!("written to {}", epub_path.display());
+                    Ok(())
+                }
+                OutputFormat::Man => {
+                    let out_dir = out.clone().unwrap_or_else(|| root.join(SYCH_MAN_DIR));
+                    save_man(&docs, &out_dir)
+                }
+            };
+            built?;
+            return save_snapshot(&docs, &root);
+        }
 
         // transpile markdown files into valid HTML
         // render and create .sych.html file
         let doc_path = root.join(SYCH_HTML);
-        save_html(&sych_cfg, &doc_path, docs_index, self.release)?;
+        docs.write_html(&doc_path, self.release)?;
+        save_atom(&docs, &root)?;
+        save_snapshot(&docs, &root)?;
 
-        // convert the absolute path to relative path for
-        // expansion on other user's machine
         if self.noopen {
-            return update_references_in_cfg(&mut sych_cfg, &root, &config_path, &markdown_files);
+            return Ok(());
         }
 
         // open file in default web browser
-        return webbrowser::open_browser(webbrowser::Browser::Default, doc_path.to_str().unwrap())
-            .map_err(anyhow::Error::from);
+        let doc_path = doc_path.canonicalize()?;
+        webbrowser::open_browser(webbrowser::Browser::Default, doc_path.to_str().unwrap())
+            .map_err(anyhow::Error::from)
     }
 }
 
-fn update_references_in_cfg(
-    sych_cfg: &mut SychConfig,
-    root: &Path,
-    config_path: &PathBuf,
-    markdown_files: &[String],
-) -> Result<()> {
-    let root_path_str = root.to_str().unwrap();
-    sych_cfg.refs = Some(
-        markdown_files
-            .iter()
-            .map(|f| f.replace(root_path_str, "."))
-            .collect(),
-    );
-    let updated_sych_cfg = toml::to_string_pretty(&sych_cfg)?;
-    std::fs::write(config_path, updated_sych_cfg).map_err(anyhow::Error::from)
-}
-
-fn index_markdown_files(
-    sych_cfg: &SychConfig,
-    markdown_files: &[String],
-    docs_index: &mut IndexedBlockMap,
-) -> Result<()> {
-    'md_loop: for md_file_path in markdown_files {
-        if let Some(doc) = sych_cfg.doc.as_ref() {
-            if let Some(exclude) = doc.exclude.as_ref() {
-                for search_path in exclude {
-                    if md_file_path.contains(search_path) {
-                        break 'md_loop;
-                    }
-                }
-            }
+/// prints the result of `sych check` and fails when there are errors
+/// (or warnings, in strict mode) so that it can gate merges
+fn report_diagnostics(diagnostics: Vec<Diagnostic>, json: bool, strict: bool) -> Result<()> {
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+
+    if json {
+        let report = serde_json::json!({
+            "errors": errors,
+            "warnings": warnings,
+            "diagnostics": diagnostics,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{diagnostic}");
         }
+        println!("{errors} error(s), {warnings} warning(s)");
+    }
 
-        let md_content = std::fs::read_to_string(md_file_path)?;
-        // convert markdown content to vec of markdown::Block
-        let tokens = markdown::tokenize(&md_content);
-        // index all markdown files to convert into sections
-        // all Header2 tags will be converted to section headers on the left
-        // and all content between the Header2 will be the children of
-        // corresponding section
-        super::indexer::create_index(tokens, docs_index);
+    if errors > 0 || (strict && warnings > 0) {
+        return Err(anyhow::Error::msg(format!(
+            "check failed with {errors} error(s) and {warnings} warning(s)"
+        )));
     }
+    Ok(())
+}
 
+/// prints every section title which was declared by more than one file
+/// and fails if any of them is configured with the `error` strategy
+fn report_collisions(docs: &Docs) -> Result<()> {
+    for collision in docs.collisions() {
+        let action = match collision.strategy {
+            MergeStrategy::Merge => "merged",
+            MergeStrategy::Separate => "kept separate",
+            MergeStrategy::Error => "error",
+            _ => "duplicated",
+        };
+        println!("duplicate section \"{}\" ({action}):", collision.title);
+        for source in &collision.sources {
+            println!("  - {source}");
+        }
+    }
+    docs.check_collisions().map_err(anyhow::Error::from)
+}
+
+/// writes every section into one file and prints how big it is, so it
+/// can be checked against the context size of review tools
+fn save_text(docs: &Docs, text_path: &Path, flavor: TextFlavor) -> Result<()> {
+    let (text, stats) = docs.text(flavor);
+    fs::write(text_path, text)?;
+
+    println!("written to {}", text_path.display());
+    println!(
+        "{} sections, {} words, {} characters, ~{} tokens",
+        stats.sections.len(),
+        stats.words,
+        stats.characters,
+        TextStats::estimated_tokens(stats.characters)
+    );
+    let mut largest = stats.sections.clone();
+    largest.sort_by_key(|(_, characters)| std::cmp::Reverse(*characters));
+    for (title, characters) in largest.iter().take(5) {
+        println!(
+            "  ~{:>6} tokens  {}",
+            TextStats::estimated_tokens(*characters),
+            title
+        );
+    }
     Ok(())
 }
 
-fn save_html(
-    sych_cfg: &SychConfig,
-    doc_path: &PathBuf,
-    docs_index: IndexedBlockMap,
-    is_release: bool,
-) -> Result<()> {
-    let mut reg = handlebars::Handlebars::new();
-    reg.register_template_string(SYCH_HBS_NAME, HBS_FILE)?;
-
-    let mut html = reg.render(SYCH_HBS_NAME, &Doc::generate(sych_cfg, docs_index))?;
-    if is_release {
-        let mut cfg = minify_html::Cfg::spec_compliant();
-        cfg.minify_js = true;
-        cfg.minify_css = true;
-        let minified_bytes = minify_html::minify(html.as_bytes(), &cfg);
-        html = std::str::from_utf8(&minified_bytes)
-            .map_err(|e| anyhow::Error::msg(e.to_string()))?
-            .to_owned();
+/// the feed of the changes next to .sych.html, when `[changelog]` is set
+fn save_atom(docs: &Docs, root: &Path) -> Result<()> {
+    if let Some(feed) = docs.atom(false) {
+        fs::write(root.join(SYCH_ATOM), feed)?;
     }
-    std::fs::write(doc_path, html).map_err(anyhow::Error::from)
+    Ok(())
 }
 
-fn initialize(cwd: &PathBuf) -> Result<()> {
-    std::fs::write(cwd, SYCH_INIT_DATA).map_err(anyhow::Error::from)
+/// the next build compares with this one, unless `[changelog]` compares
+/// two fixed revisions
+fn save_snapshot(docs: &Docs, root: &Path) -> Result<()> {
+    let Some(changelog) = docs.config().changelog.as_ref() else {
+        return Ok(());
+    };
+    if changelog.since.is_none() && changelog.until.is_none() {
+        docs.write_snapshot(&root.join(SYCH_SNAPSHOT))?;
+    }
+    Ok(())
+}
+
+/// writes one man page per section, e.g. `man -l .sych-man/payments.7`
+fn save_man(docs: &Docs, out_dir: &Path) -> Result<()> {
+    fs::create_dir_all(out_dir)?;
+    for (file, page) in docs.man_pages() {
+        fs::write(out_dir.join(file), page)?;
+    }
+    println!("man pages written to {}", out_dir.display());
+    Ok(())
+}
+
+/// diagnostics are colored unless stderr is redirected or NO_COLOR is set
+pub fn stderr_color() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none()
 }
 
-fn get_cwd() -> Result<PathBuf> {
-    std::env::current_dir().map_err(anyhow::Error::from)
+fn initialize(cwd: &PathBuf) -> Result<()> {
+    std::fs::write(cwd, SYCH_INIT_DATA).map_err(anyhow::Error::from)
 }
 
 /// acts as a gatekeeper for verifying the extensions provided by the users
-fn _validate_config(sych_cfg: &SychConfig) -> Result<()> {
+fn _validate_config(sych_cfg: &sych::SychConfig) -> Result<()> {
     if let Some(extensions) = sych_cfg.extensions.as_ref() {
         for ext in extensions.values() {
             if !ext.url.starts_with("https://ext.sych.com")
@@ -1,43 +1,20 @@
-use std::collections::HashMap;
-
-use serde::{Deserialize, Serialize};
-
 mod cli;
-mod indexer;
-mod transpiler;
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Doc {
-    pub root: Option<String>,
-    pub exclude: Option<Vec<String>>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Meta {
-    pub title: String,
-    pub authors: Vec<String>,
-    pub version: String,
-    pub description: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct ExtensionMeta {
-    pub url: String,
-    pub opts: Option<HashMap<String, String>>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct SychConfig {
-    pub meta: Meta,
-    pub doc: Option<Doc>,
-    pub refs: Option<Vec<String>>,
-    pub extensions: Option<HashMap<String, ExtensionMeta>>,
-}
+mod pager;
 
 fn main() {
     let app = cli::SychCLI::load();
     if let Err(e) = app.execute() {
-        eprintln!("error: {}", e);
-        std::process::exit(1);
+        // errors of the library point into the files and have their own
+        // exit codes, see `sych::Error::exit_code`
+        match e.downcast_ref::<sych::Error>() {
+            Some(error) => {
+                eprint!("{}", error.report(cli::stderr_color()));
+                std::process::exit(error.exit_code());
+            }
+            None => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
     }
 }
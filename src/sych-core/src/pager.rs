@@ -0,0 +1,38 @@
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+
+/// shows the text through `$PAGER` (less by default) when writing to a
+/// terminal, otherwise or when the pager cannot be started it is printed
+pub(crate) fn page(text: &str, no_pager: bool) -> Result<()> {
+    if no_pager || !std::io::stdout().is_terminal() {
+        print!("{text}");
+        return Ok(());
+    }
+
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_owned());
+    let mut args = pager.split_whitespace();
+    let Some(program) = args.next() else {
+        print!("{text}");
+        return Ok(());
+    };
+    let mut command = Command::new(program);
+    command.args(args).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        // quit when it fits on one screen and keep the colors
+        command.env("LESS", "FRX");
+    }
+
+    match command.spawn() {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // the pager may be closed before everything is written
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait()?;
+        }
+        Err(_) => print!("{text}"),
+    }
+    Ok(())
+}
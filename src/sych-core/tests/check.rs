@@ -0,0 +1,73 @@
+use std::process::Command;
+
+#[test]
+fn reports_missing_snippets_along_with_other_problems_as_json() {
+    let dir = std::env::temp_dir().join(format!("sych-check-snippets-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("sych.toml"), "[meta]\ntitle = \"t\"\n").unwrap();
+    std::fs::write(dir.join("cli.rs"), "pub struct Cli;\n").unwrap();
+    std::fs::write(
+        dir.join("a.md"),
+        "## Cli\n\n[setup](b.md)\n\n```rust file=cli.rs region=greet\n```\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_sych-core"))
+        .args(["check", "--json"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let codes: Vec<&str> = report["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["code"].as_str().unwrap())
+        .collect();
+    assert!(codes.contains(&"missing-snippet"), "{codes:?}");
+    assert!(codes.contains(&"broken-link"), "{codes:?}");
+
+    // building needs the snippet
+    let output = Command::new(env!("CARGO_BIN_EXE_sych-core"))
+        .args(["build"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("region \"greet\""));
+}
+
+#[test]
+fn fails_on_warnings_only_when_strict() {
+    let dir = std::env::temp_dir().join(format!("sych-check-strict-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("sych.toml"), "[meta]\ntitle = \"t\"\n").unwrap();
+    std::fs::write(dir.join("a.md"), "## Setup\n\nRun it.\n\n## Empty\n").unwrap();
+    let check = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_sych-core"))
+            .arg("check")
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+
+    let output = check(&[]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("0 error(s), 1 warning(s)"));
+    assert_eq!(check(&["--strict"]).status.code(), Some(1));
+
+    for (args, code) in [(&["--json"][..], 0), (&["--json", "--strict"][..], 1)] {
+        let output = check(args);
+        assert_eq!(output.status.code(), Some(code), "{args:?}");
+        // the report stays parseable when the check fails
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(report["errors"], 0);
+        assert_eq!(report["warnings"], 1);
+        assert_eq!(report["diagnostics"][0]["code"], "empty-section");
+        assert_eq!(report["diagnostics"][0]["line"], 5);
+    }
+}
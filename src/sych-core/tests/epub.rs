@@ -0,0 +1,189 @@
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+
+use zip::{CompressionMethod, ZipArchive};
+
+/// a 1x1 transparent png
+const PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x00, 0x01, 0x00, 0x00,
+    0x05, 0x00, 0x01, 0x0d, 0x0a, 0x2d, 0xb4, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae,
+    0x42, 0x60, 0x82,
+];
+
+struct Epub {
+    archive: ZipArchive<File>,
+}
+
+impl Epub {
+    fn build(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("sych-epub-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("img")).unwrap();
+        std::fs::write(
+            dir.join("sych.toml"),
+            "[meta]\ntitle = \"Handbook\"\nauthors = [\"Ada\", \"Linus\"]\nversion = \"1.2.0\"\ndescription = \"How we <work> & ship\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("guide.md"),
+            "## Guide\n\n### Setup\n\nRead [the api](api.md#errors) first.\n\n![diagram](img/flow.png)\n\n```rust\nfn main() {}\n```\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("api.md"),
+            "## API\n\n### Errors\n\nEvery call returns a result, a < b && c.\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("img/flow.png"), PNG).unwrap();
+
+        let out = dir.join("book.epub");
+        let status = Command::new(env!("CARGO_BIN_EXE_sych-core"))
+            .args(["build", "--format", "epub", "--out"])
+            .arg(&out)
+            .current_dir(&dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let archive = ZipArchive::new(File::open(out).unwrap()).unwrap();
+        Epub { archive }
+    }
+
+    fn read(&mut self, name: &str) -> String {
+        let mut content = String::new();
+        self.archive
+            .by_name(name)
+            .unwrap_or_else(|_| panic!("{name} is missing"))
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+}
+
+/// values of every `attr="..."` inside the document
+fn attributes(document: &str, attr: &str) -> Vec<String> {
+    let pattern = format!(" {attr}=\"");
+    document
+        .split(&pattern)
+        .skip(1)
+        .map(|rest| rest[..rest.find('"').unwrap()].to_owned())
+        .collect()
+}
+
+#[test]
+fn starts_with_an_uncompressed_mimetype() {
+    let mut epub = Epub::build("starts");
+    let first = epub.archive.by_index(0).unwrap();
+    assert_eq!(first.name(), "mimetype");
+    assert_eq!(first.compression(), CompressionMethod::Stored);
+    drop(first);
+    assert_eq!(epub.read("mimetype"), "application/epub+zip");
+
+    let container = epub.read("META-INF/container.xml");
+    assert!(container.contains("full-path=\"OEBPS/content.opf\""));
+}
+
+#[test]
+fn manifest_matches_the_archive() {
+    let mut epub = Epub::build("manifest");
+    let opf = epub.read("OEBPS/content.opf");
+
+    let names: Vec<String> = epub.archive.file_names().map(String::from).collect();
+    for href in attributes(&opf, "href") {
+        let path = format!("OEBPS/{href}");
+        assert!(names.contains(&path), "{path} is in the manifest only");
+    }
+    for name in names.iter().filter(|n| n.starts_with("OEBPS/")) {
+        let href = name.trim_start_matches("OEBPS/");
+        assert!(
+            name.ends_with(".opf") || opf.contains(&format!("href=\"{href}\"")),
+            "{name} is not in the manifest"
+        );
+    }
+
+    assert!(opf.contains("properties=\"nav\""));
+    assert!(opf.contains("<meta property=\"dcterms:modified\">"));
+    assert!(opf.contains("media-type=\"image/png\""));
+
+    // chapters follow the order of the sections
+    let spine = &opf[opf.find("<spine>").unwrap()..];
+    assert_eq!(
+        attributes(spine, "idref"),
+        vec!["title", "chapter-0", "chapter-1"]
+    );
+    let chapters: Vec<String> = attributes(&opf, "href")
+        .into_iter()
+        .filter(|href| href.ends_with(".xhtml") && href != "nav.xhtml")
+        .collect();
+    let nav: Vec<String> = attributes(&epub.read("OEBPS/nav.xhtml"), "href")
+        .into_iter()
+        .filter(|href| href.ends_with(".xhtml"))
+        .collect();
+    assert_eq!(chapters, nav);
+}
+
+#[test]
+fn metadata_comes_from_the_config() {
+    let mut epub = Epub::build("metadata");
+    let opf = epub.read("OEBPS/content.opf");
+    assert!(opf.contains("<dc:title>Handbook</dc:title>"));
+    assert!(opf.contains("<dc:creator>Ada</dc:creator>"));
+    assert!(opf.contains("<dc:creator>Linus</dc:creator>"));
+    assert!(opf.contains("<dc:description>How we &lt;work&gt; &amp; ship</dc:description>"));
+    assert!(opf.contains("content=\"1.2.0\""));
+    assert!(opf.contains("<dc:identifier id=\"uid\">urn:sych:handbook:1.2.0</dc:identifier>"));
+}
+
+#[test]
+fn nav_and_links_point_to_existing_ids() {
+    let mut epub = Epub::build("nav");
+    let nav = epub.read("OEBPS/nav.xhtml");
+    assert!(nav.contains("epub:type=\"toc\""));
+
+    let guide = epub.read("OEBPS/guide.xhtml");
+    let links: Vec<String> = attributes(&nav, "href")
+        .into_iter()
+        .chain(
+            attributes(&guide, "href")
+                .into_iter()
+                .filter(|h| h.contains(".xhtml")),
+        )
+        .collect();
+    assert!(links.contains(&"api.xhtml#s-api--errors".to_owned()));
+
+    for link in links {
+        let (file, id) = link.split_once('#').unwrap_or((&link, ""));
+        let target = epub.read(&format!("OEBPS/{file}"));
+        if !id.is_empty() {
+            assert!(
+                target.contains(&format!("id=\"{id}\"")),
+                "{link} is dangling"
+            );
+        }
+    }
+}
+
+#[test]
+fn chapters_embed_images_and_highlighted_code() {
+    let mut epub = Epub::build("chapters");
+    let guide = epub.read("OEBPS/guide.xhtml");
+    assert!(guide.starts_with("<?xml"));
+    assert!(guide.contains("<img src=\"images/0.png\" alt=\"diagram\"/>"));
+    assert!(guide.contains("<span class=\"source rust\">"));
+
+    let mut image = vec![];
+    epub.archive
+        .by_name("OEBPS/images/0.png")
+        .unwrap()
+        .read_to_end(&mut image)
+        .unwrap();
+    assert_eq!(image, PNG);
+
+    // text is escaped for xhtml
+    let api = epub.read("OEBPS/api.xhtml");
+    assert!(api.contains("a &lt; b &amp;&amp; c."));
+    assert!(epub.read("OEBPS/style.css").contains(".source"));
+}
@@ -0,0 +1,233 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// stand-in for the internet, answers every request according to its path
+struct MockServer {
+    base: String,
+    /// when every request arrived, along with its method and path
+    requests: Arc<Mutex<Vec<(Instant, String)>>>,
+}
+
+impl MockServer {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let arrived = Instant::now();
+                let log = log.clone();
+                // connections are answered at the same time, like a real
+                // server would
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    // drain the headers
+                    let mut header = String::new();
+                    while reader.read_line(&mut header).unwrap() > 2 {
+                        header.clear();
+                    }
+
+                    let mut parts = request_line.split_whitespace();
+                    let method = parts.next().unwrap_or_default();
+                    let path = parts.next().unwrap_or_default();
+                    log.lock()
+                        .unwrap()
+                        .push((arrived, format!("{method} {path}")));
+                    if path.starts_with("/slow") {
+                        thread::sleep(SLOW_RESPONSE);
+                    }
+                    let response = match (method, path.split('?').next().unwrap_or_default()) {
+                    (_, "/ok" | "/slow") => "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+                    (_, "/moved") => {
+                        "HTTP/1.1 301 Moved Permanently\r\nLocation: /ok\r\nContent-Length: 0\r\n\r\n"
+                    }
+                    ("HEAD", "/no-head") => {
+                        "HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\n\r\n"
+                    }
+                    ("GET", "/no-head") => "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+                    _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+                };
+                    let _ = stream.write_all(response.as_bytes());
+                });
+            }
+        });
+
+        MockServer { base, requests }
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// when each request arrived, relative to the first one
+    fn arrivals(&self) -> Vec<Duration> {
+        let mut requests = self.requests.lock().unwrap().clone();
+        requests.sort_by_key(|(arrived, _)| *arrived);
+        let first = requests.first().map(|(arrived, _)| *arrived);
+        requests
+            .iter()
+            .map(|(arrived, _)| *arrived - first.unwrap())
+            .collect()
+    }
+}
+
+const SLOW_RESPONSE: Duration = Duration::from_millis(600);
+
+fn project(name: &str, base: &str, extra_cfg: &str) -> PathBuf {
+    let paths = ["ok", "moved", "no head", "missing"].map(|name| {
+        let path = name.replace(' ', "-");
+        format!("[{name}]({base}/{path})")
+    });
+    project_linking(name, extra_cfg, &paths)
+}
+
+/// a project whose notes are made of the given links, one per paragraph
+fn project_linking(name: &str, extra_cfg: &str, links: &[String]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sych-external-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("sych.toml"),
+        format!(
+            "[meta]\ntitle = \"t\"\nauthors = []\nversion = \"0\"\ndescription = \"d\"\n{extra_cfg}"
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("NOTES.md"),
+        format!("## Links\n\n{}\n", links.join("\n\n")),
+    )
+    .unwrap();
+    dir
+}
+
+fn check(dir: &PathBuf) -> (bool, serde_json::Value) {
+    let output = Command::new(env!("CARGO_BIN_EXE_sych-core"))
+        .args(["check", "--external", "--json"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    let report = serde_json::from_slice(&output.stdout).unwrap();
+    (output.status.success(), report)
+}
+
+fn broken_urls(report: &serde_json::Value) -> Vec<String> {
+    report["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|d| d["code"] == "broken-external-link")
+        .map(|d| d["message"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn reports_only_unreachable_links() {
+    let server = MockServer::start();
+    let dir = project("broken", &server.base, "");
+
+    let (success, report) = check(&dir);
+    let broken = broken_urls(&report);
+    assert!(!success);
+    assert_eq!(broken.len(), 1, "{broken:?}");
+    assert!(broken[0].contains("/missing responded with 404"));
+    assert_eq!(report["diagnostics"][0]["line"], 9);
+}
+
+#[test]
+fn reuses_cached_results_between_runs() {
+    let server = MockServer::start();
+    let dir = project("cache", &server.base, "");
+
+    check(&dir);
+    let first_run = server.requests();
+    check(&dir);
+    let second_run = server.requests() - first_run;
+
+    // only the failing url is requested again (HEAD, then GET)
+    assert_eq!(second_run, 2);
+    assert!(dir.join(".sych-cache/external-links.json").exists());
+}
+
+#[test]
+fn skips_ignored_and_not_allowed_links() {
+    let server = MockServer::start();
+    let cfg = format!(
+        "[check.external]\nallow = [\"{0}/*\"]\nignore = [\"{0}/miss*\"]\n",
+        server.base
+    );
+    let dir = project("ignore", &server.base, &cfg);
+
+    let (success, report) = check(&dir);
+    assert!(success);
+    assert!(broken_urls(&report).is_empty());
+}
+
+#[test]
+fn requests_several_urls_at_the_same_time() {
+    let server = MockServer::start();
+    let links: Vec<String> = (0..4)
+        .map(|i| format!("[slow]({}/slow?{i})", server.base))
+        .collect();
+    let dir = project_linking(
+        "concurrency",
+        "[check.external]\nconcurrency = 4\nrate_limit = 0\n",
+        &links,
+    );
+
+    let started = Instant::now();
+    let (success, _) = check(&dir);
+    assert!(success);
+    let arrivals = server.arrivals();
+    assert_eq!(arrivals.len(), 4);
+    // one at a time they would arrive a slow response apart
+    assert!(arrivals[3] < SLOW_RESPONSE / 2, "{arrivals:?}");
+    assert!(started.elapsed() < SLOW_RESPONSE * 3);
+}
+
+#[test]
+fn spaces_out_requests_to_the_same_host() {
+    let server = MockServer::start();
+    let links: Vec<String> = (0..3)
+        .map(|i| format!("[ok]({}/ok?{i})", server.base))
+        .collect();
+    let dir = project_linking(
+        "rate-limit",
+        "[check.external]\nconcurrency = 4\nrate_limit = 4\n",
+        &links,
+    );
+
+    let (success, _) = check(&dir);
+    assert!(success);
+    let arrivals = server.arrivals();
+    assert_eq!(arrivals.len(), 3);
+    // 4 requests per second, with some slack for the timer
+    for pair in arrivals.windows(2) {
+        assert!(
+            pair[1] - pair[0] >= Duration::from_millis(230),
+            "{arrivals:?}"
+        );
+    }
+
+    // so slow that the next request would never be sent
+    let dir = project_linking(
+        "tiny-rate-limit",
+        "[check.external]\nrate_limit = 1e-20\n",
+        &links[..2],
+    );
+    let (success, report) = check(&dir);
+    assert!(!success);
+    let broken = broken_urls(&report);
+    assert_eq!(broken.len(), 1, "{broken:?}");
+    assert!(broken[0]
+        .ends_with("was not requested, the rate limit of its host allows no more requests"));
+}
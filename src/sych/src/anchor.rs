@@ -0,0 +1,363 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use indexmap::IndexMap;
+use markdown::{Block, ListItem, Span};
+
+use crate::indexer::Section;
+
+/// converts a title into a lowercase, url friendly anchor
+/// e.g. "Knowing the codebase!" -> "knowing-the-codebase"
+pub(crate) fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || "-_./".contains(c)) && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "section".into()
+    } else {
+        slug.into()
+    }
+}
+
+/// plain text of the spans without any markup, used for generating anchors
+pub(crate) fn spans_to_text(spans: &[Span]) -> String {
+    let mut text = String::new();
+    for span in spans {
+        match span {
+            Span::Break => text.push(' '),
+            Span::Text(t) | Span::Code(t) => text.push_str(t),
+            Span::Link(t, _, _) => text.push_str(t),
+            Span::Image(alt, _, _) => text.push_str(alt),
+            Span::Emphasis(s) | Span::Strong(s) => text.push_str(&spans_to_text(s)),
+        }
+    }
+    text
+}
+
+/// hands out unique slugs, a repeated slug gets a numbered suffix
+/// in the order it was seen: "retries", "retries-1", "retries-2"
+#[derive(Debug, Default)]
+pub(crate) struct Slugger {
+    seen: HashSet<String>,
+}
+
+impl Slugger {
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slugify(text);
+        let mut slug = base.clone();
+        let mut count = 0;
+        while self.seen.contains(&slug) {
+            count += 1;
+            slug = format!("{}-{}", base, count);
+        }
+        self.seen.insert(slug.clone());
+        slug
+    }
+}
+
+/// calls `f` for every heading in the order in which they are rendered,
+/// including the headings nested inside blockquotes and lists
+pub(crate) fn for_each_heading<'a>(blocks: &'a [Block], f: &mut impl FnMut(&'a [Span])) {
+    for block in blocks {
+        match block {
+            Block::Header(h, _) => f(h),
+            Block::Blockquote(bq) => for_each_heading(bq, f),
+            Block::OrderedList(items, _) | Block::UnorderedList(items) => {
+                for item in items {
+                    if let ListItem::Paragraph(p) = item {
+                        for_each_heading(p, f);
+                    }
+                }
+            }
+            _ => continue,
+        }
+    }
+}
+
+/// what a link inside the markdown points to
+#[derive(Debug, PartialEq)]
+pub(crate) enum Link {
+    /// anything with a scheme, e.g. https: or mailto:
+    External,
+    /// anchor of a section or heading inside the generated docs
+    Internal(String),
+    /// a markdown file which is not part of the docs
+    Broken,
+    /// a heading which does not exist in an indexed markdown file
+    MissingAnchor,
+    /// any other relative link, e.g. images or source files
+    Asset,
+}
+
+/// anchors of every section and heading, computed up front so that
+/// links between markdown files can be resolved while rendering
+#[derive(Debug, Default)]
+pub(crate) struct Anchors {
+    /// section title -> section anchor, in navigation order
+    pub sections: IndexMap<String, String>,
+    /// markdown file -> anchor of its first section
+    files: HashMap<String, String>,
+    /// (markdown file, slug of a heading) -> anchor, repeated headings
+    /// of a file are numbered the way github numbers them, e.g. the
+    /// second "Retries" is "retries-1"
+    headings: HashMap<(String, String), String>,
+}
+
+impl Anchors {
+    pub fn build(docs_index: &IndexMap<String, Section>) -> Self {
+        let mut anchors = Anchors::default();
+        let mut section_slugs = Slugger::default();
+        // slugs are unique within a file, whatever sections it adds to
+        let mut file_slugs: HashMap<String, Slugger> = HashMap::new();
+        for (title, section) in docs_index {
+            let id = section_slugs.slug(title);
+            // the same order as the transpiler hands them out
+            let mut heading_slugs = Slugger::default();
+            for part in &section.parts {
+                anchors
+                    .files
+                    .entry(part.source.clone())
+                    .or_insert_with(|| id.clone());
+                let file_slugs = file_slugs.entry(part.source.clone()).or_default();
+                anchors.headings.insert(
+                    (part.source.clone(), file_slugs.slug(&part.title)),
+                    id.clone(),
+                );
+
+                if section.is_merged() {
+                    // the sub-heading naming the file is not part of it
+                    heading_slugs.slug(&part.source);
+                }
+                for_each_heading(&part.blocks, &mut |heading| {
+                    let text = spans_to_text(heading);
+                    let anchor = format!("{}/{}", id, heading_slugs.slug(&text));
+                    anchors
+                        .headings
+                        .insert((part.source.clone(), file_slugs.slug(&text)), anchor);
+                });
+            }
+            anchors.sections.insert(title.clone(), id);
+        }
+        anchors
+    }
+
+    /// resolves a link found in `source` (path relative to the docs root)
+    pub fn resolve(&self, source: &str, href: &str) -> Link {
+        if is_external(href) {
+            return Link::External;
+        }
+
+        let (path, fragment) = match href.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment)),
+            None => (href, None),
+        };
+        let target = if path.is_empty() {
+            // "#retries" points to a heading of the same file
+            source.to_owned()
+        } else {
+            match resolve_path(source, path) {
+                Some(target) => target,
+                None if is_markdown(path) => return Link::Broken,
+                None => return Link::Asset,
+            }
+        };
+        if !is_markdown(&target) {
+            return Link::Asset;
+        }
+
+        if !self.files.contains_key(&target) {
+            return Link::Broken;
+        }
+        let anchor = match fragment {
+            Some(fragment) if !fragment.is_empty() => {
+                self.headings.get(&(target, fragment.to_owned())).cloned()
+            }
+            _ => self.files.get(&target).cloned(),
+        };
+        anchor.map(Link::Internal).unwrap_or(Link::MissingAnchor)
+    }
+
+    /// title of the section an anchor belongs to
+    pub fn section_title(&self, anchor: &str) -> Option<&str> {
+        let id = anchor.split('/').next().unwrap_or_default();
+        self.sections
+            .iter()
+            .find(|(_, section_id)| *section_id == id)
+            .map(|(title, _)| title.as_str())
+    }
+
+    /// finds a section by its title (ignoring case) or by its anchor
+    pub fn find_section(&self, query: &str) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|(title, id)| title.eq_ignore_ascii_case(query) || *id == query)
+            .map(|(title, _)| title.as_str())
+    }
+}
+
+fn is_external(href: &str) -> bool {
+    if href.starts_with("//") {
+        return true;
+    }
+    // a scheme is made of letters followed by a colon, e.g. "https:"
+    match href.split_once(':') {
+        Some((scheme, _)) => {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }
+        None => false,
+    }
+}
+
+fn is_markdown(path: &str) -> bool {
+    path.to_lowercase().ends_with(".md")
+}
+
+/// joins `path` to the directory of `source` without touching the file
+/// system, `None` when the result escapes the docs root
+pub(crate) fn resolve_path(source: &str, path: &str) -> Option<String> {
+    let joined = match path.strip_prefix('/') {
+        Some(from_root) => PathBuf::from(from_root),
+        None => Path::new(source)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(path),
+    };
+
+    let mut resolved: Vec<&str> = vec![];
+    for component in joined.components() {
+        match component {
+            Component::Normal(c) => resolved.push(c.to_str()?),
+            Component::ParentDir => {
+                resolved.pop()?;
+            }
+            _ => continue,
+        }
+    }
+    Some(resolved.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontmatter::FrontMatter;
+    use crate::indexer::SectionPart;
+
+    fn part(source: &str, title: &str, headings: &[&str]) -> SectionPart {
+        let blocks = headings
+            .iter()
+            .map(|heading| Block::Header(vec![Span::Text(heading.to_string())], 3))
+            .collect();
+        SectionPart {
+            title: title.to_owned(),
+            source: source.to_owned(),
+            front_matter: FrontMatter::default(),
+            blocks,
+            git: None,
+            stale: None,
+        }
+    }
+
+    fn anchors() -> Anchors {
+        let mut docs_index = IndexMap::new();
+        for part in [
+            part("guide/payments.md", "Payments", &["Retries", "Retries"]),
+            part("guide/payments.md", "Payments API", &["Retries"]),
+            part("refunds.md", "Refunds", &["Limits"]),
+        ] {
+            let section = Section { parts: vec![part] };
+            docs_index.insert(section.parts[0].title.clone(), section);
+        }
+        Anchors::build(&docs_index)
+    }
+
+    #[test]
+    fn slugifies_titles() {
+        assert_eq!(slugify("Knowing the codebase!"), "knowing-the-codebase");
+        assert_eq!(slugify("  Getting   started  "), "getting-started");
+        assert_eq!(slugify("C++ & Rust"), "c-rust");
+        assert_eq!(slugify("src/lib.rs: snake_case"), "src-lib-rs-snake-case");
+        assert_eq!(slugify("a -- b"), "a-b");
+        assert_eq!(slugify("Café ÜBERSICHT"), "café-übersicht");
+        assert_eq!(slugify("日本語のガイド"), "日本語のガイド");
+        assert_eq!(slugify("?!"), "section");
+        assert_eq!(slugify(""), "section");
+    }
+
+    #[test]
+    fn numbers_repeated_slugs() {
+        let mut slugs = Slugger::default();
+        assert_eq!(slugs.slug("Retries"), "retries");
+        assert_eq!(slugs.slug("retries!"), "retries-1");
+        assert_eq!(slugs.slug("Retries"), "retries-2");
+        // a title which looks like a numbered one is numbered in turn
+        let mut slugs = Slugger::default();
+        assert_eq!(slugs.slug("a"), "a");
+        assert_eq!(slugs.slug("a 1"), "a-1");
+        assert_eq!(slugs.slug("a"), "a-2");
+        assert_eq!(slugs.slug("?"), "section");
+        assert_eq!(slugs.slug("!"), "section-1");
+    }
+
+    #[test]
+    fn resolves_paths_inside_the_root() {
+        let source = "guide/payments.md";
+        assert_eq!(resolve_path(source, "setup.md").unwrap(), "guide/setup.md");
+        assert_eq!(resolve_path(source, "../refunds.md").unwrap(), "refunds.md");
+        assert_eq!(resolve_path(source, "/refunds.md").unwrap(), "refunds.md");
+        assert_eq!(resolve_path(source, "./a/../b.md").unwrap(), "guide/b.md");
+        assert_eq!(
+            resolve_path("refunds.md", "img/a.png").unwrap(),
+            "img/a.png"
+        );
+        assert_eq!(resolve_path(source, "../../outside.md"), None);
+        assert_eq!(resolve_path(source, "/../outside.md"), None);
+    }
+
+    #[test]
+    fn resolves_links_to_anchors() {
+        let anchors = anchors();
+        let resolve = |href| anchors.resolve("guide/payments.md", href);
+        let internal = |anchor: &str| Link::Internal(anchor.to_owned());
+        assert_eq!(resolve("../refunds.md"), internal("refunds"));
+        assert_eq!(resolve("/refunds.md#limits"), internal("refunds/limits"));
+        assert_eq!(resolve("#payments"), internal("payments"));
+        assert_eq!(
+            resolve("payments.md#payments-api"),
+            internal("payments-api")
+        );
+        assert_eq!(resolve("#retries"), internal("payments/retries"));
+        assert_eq!(resolve(""), internal("payments"));
+
+        assert_eq!(resolve("refunds.md"), Link::Broken);
+        assert_eq!(resolve("../../refunds.md"), Link::Broken);
+        assert_eq!(resolve("../refunds.md#nope"), Link::MissingAnchor);
+        assert_eq!(resolve("https://example.com/a.md"), Link::External);
+        assert_eq!(resolve("mailto:ana@example.com"), Link::External);
+        assert_eq!(resolve("//cdn.example.com/a.js"), Link::External);
+        assert_eq!(resolve("../src/lib.rs"), Link::Asset);
+        assert_eq!(resolve("../../logo.png"), Link::Asset);
+    }
+
+    #[test]
+    fn numbers_repeated_headings_of_a_file_like_github() {
+        let anchors = anchors();
+        let resolve = |href| anchors.resolve("guide/payments.md", href);
+        let internal = |anchor: &str| Link::Internal(anchor.to_owned());
+        assert_eq!(resolve("#retries-1"), internal("payments/retries-1"));
+        // the third "Retries" of the file is the first of its section
+        assert_eq!(resolve("#retries-2"), internal("payments-api/retries"));
+        assert_eq!(resolve("#retries-3"), Link::MissingAnchor);
+        // the fragment is matched as written
+        assert_eq!(resolve("#Retries"), Link::MissingAnchor);
+    }
+}
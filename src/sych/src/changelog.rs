@@ -0,0 +1,390 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use indexmap::IndexMap;
+use markdown::{Block, ListItem, Span};
+use serde::{Deserialize, Serialize};
+
+use crate::anchor::slugify;
+use crate::epub::{escape, timestamp};
+use crate::export::blocks_text;
+use crate::indexer::Section;
+use crate::{ChangelogConfig, Meta};
+
+/// where a build leaves the text of its sections for the next build to
+/// compare with, inside the root
+pub const SYCH_SNAPSHOT: &str = ".sych-snapshot.json";
+
+const DEFAULT_TITLE: &str = "What changed";
+
+/// lines of context around every change of a diff
+const CONTEXT: usize = 1;
+
+/// cells of the table the longest common lines are looked up in, bigger
+/// sections are shown as removed and added as a whole
+const MAX_TABLE: usize = 1 << 22;
+
+/// how a section changed between two versions of the docs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct Change {
+    pub title: String,
+    pub kind: ChangeKind,
+    /// lines of a diff of the plain text of a modified section, starting
+    /// with `+` when added, `-` when removed and a space around them,
+    /// distant changes are separated by `@@`
+    pub diff: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// the changes the docs were indexed with
+#[derive(Debug, Default)]
+pub(crate) struct Changelog {
+    pub changes: Vec<Change>,
+    /// when the newer of the compared versions was made, in seconds
+    /// since the unix epoch
+    pub updated: i64,
+}
+
+/// plain text of every section made from markdown files, what two
+/// versions of the docs are compared by
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct Snapshot {
+    /// seconds since the unix epoch
+    pub time: i64,
+    pub sections: Vec<SnapshotSection>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct SnapshotSection {
+    pub title: String,
+    pub text: String,
+}
+
+impl Snapshot {
+    /// generated and harvested sections are left out, they follow the
+    /// code rather than the notes
+    pub fn of(sections: &IndexMap<String, Section>, time: i64) -> Self {
+        let sections = sections
+            .iter()
+            .filter_map(|(title, section)| {
+                let parts: Vec<_> = section
+                    .parts
+                    .iter()
+                    .filter(|part| part.source.ends_with(".md"))
+                    .collect();
+                if parts.is_empty() {
+                    return None;
+                }
+                let text = parts
+                    .iter()
+                    .map(|part| blocks_text(&part.blocks))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Some(SnapshotSection {
+                    title: title.clone(),
+                    text,
+                })
+            })
+            .collect();
+        Snapshot { time, sections }
+    }
+
+    fn text(&self, title: &str) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|section| section.title == title)
+            .map(|section| section.text.as_str())
+    }
+}
+
+/// seconds since the unix epoch
+pub(crate) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// added and modified sections in the order of `new`, followed by the
+/// removed ones in the order of `old`
+pub(crate) fn compare(old: &Snapshot, new: &Snapshot) -> Vec<Change> {
+    let mut changes = vec![];
+    for section in &new.sections {
+        match old.text(&section.title) {
+            None => changes.push(Change {
+                title: section.title.clone(),
+                kind: ChangeKind::Added,
+                diff: vec![],
+            }),
+            Some(text) if text != section.text => {
+                let diff = diff(text, &section.text);
+                // only blank lines moved around
+                if diff.is_empty() {
+                    continue;
+                }
+                changes.push(Change {
+                    title: section.title.clone(),
+                    kind: ChangeKind::Modified,
+                    diff,
+                });
+            }
+            Some(_) => continue,
+        }
+    }
+    for section in &old.sections {
+        if new.text(&section.title).is_none() {
+            changes.push(Change {
+                title: section.title.clone(),
+                kind: ChangeKind::Removed,
+                diff: vec![],
+            });
+        }
+    }
+    changes
+}
+
+/// the changed lines with their context, blank lines are left out as
+/// they only separate the blocks
+fn diff(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().filter(|line| !line.trim().is_empty()).collect();
+    let new: Vec<&str> = new.lines().filter(|line| !line.trim().is_empty()).collect();
+
+    // (sign, line), the lines both have in common are kept
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    let mut ops: Vec<(char, &str)> = old[..prefix].iter().map(|line| (' ', *line)).collect();
+    ops.extend(middle_ops(old_middle, new_middle));
+    ops.extend(old[old.len() - suffix..].iter().map(|line| (' ', *line)));
+
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (sign, _))| *sign != ' ')
+        .map(|(i, _)| i)
+        .collect();
+    let mut lines = vec![];
+    let mut last_shown: Option<usize> = None;
+    for (i, (sign, line)) in ops.iter().enumerate() {
+        let near = changed.iter().any(|&c| c.abs_diff(i) <= CONTEXT);
+        if !near {
+            continue;
+        }
+        if last_shown.is_some_and(|last| last + 1 < i) {
+            lines.push("@@".to_owned());
+        }
+        lines.push(format!("{sign}{line}"));
+        last_shown = Some(i);
+    }
+    lines
+}
+
+/// removed and added lines between the common prefix and suffix, from
+/// the longest common subsequence of both
+fn middle_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str)> {
+    if (old.len() + 1) * (new.len() + 1) > MAX_TABLE {
+        return old
+            .iter()
+            .map(|line| ('-', *line))
+            .chain(new.iter().map(|line| ('+', *line)))
+            .collect();
+    }
+
+    // common[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            ops.push(('-', old[i]));
+            i += 1;
+        } else {
+            ops.push(('+', new[j]));
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// the generated section listing the changes between `old` and `new`,
+/// which describe the compared versions, `None` for `old` when there is
+/// nothing to compare with
+pub(crate) fn changelog_blocks(
+    changelog: &ChangelogConfig,
+    changes: &[Change],
+    old: Option<&str>,
+    new: &str,
+    sections: &IndexMap<String, Section>,
+) -> Vec<Block> {
+    let title = changelog.title.as_deref().unwrap_or(DEFAULT_TITLE);
+    let mut blocks = vec![Block::Header(vec![Span::Text(title.to_owned())], 2)];
+    let summary = match old {
+        None => "There is no earlier build to compare with yet, the next build is compared with this one.".to_owned(),
+        Some(old) if changes.is_empty() => format!("Nothing changed between {old} and {new}."),
+        Some(old) => format!("Changes of the docs between {old} and {new}."),
+    };
+    blocks.push(Block::Paragraph(vec![Span::Text(summary)]));
+
+    let link = |title: &str| {
+        let part = sections
+            .get(title)
+            .and_then(|section| {
+                section
+                    .parts
+                    .iter()
+                    .find(|part| part.source.ends_with(".md"))
+            })
+            .filter(|part| !part.source.starts_with(".."));
+        match part {
+            Some(part) => Span::Link(
+                title.to_owned(),
+                format!("/{}#{}", part.source, slugify(&part.title)),
+                None,
+            ),
+            None => Span::Text(title.to_owned()),
+        }
+    };
+
+    for (kind, heading) in [
+        (ChangeKind::Added, "Added"),
+        (ChangeKind::Removed, "Removed"),
+        (ChangeKind::Modified, "Modified"),
+    ] {
+        let items: Vec<ListItem> = changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .map(|change| match kind {
+                ChangeKind::Modified => ListItem::Paragraph(vec![
+                    Block::Paragraph(vec![link(&change.title)]),
+                    Block::CodeBlock(Some("diff".to_owned()), change.diff.join("\n")),
+                ]),
+                ChangeKind::Removed => ListItem::Simple(vec![Span::Text(change.title.clone())]),
+                ChangeKind::Added => ListItem::Simple(vec![link(&change.title)]),
+            })
+            .collect();
+        if !items.is_empty() {
+            blocks.push(Block::Header(vec![Span::Text(heading.to_owned())], 3));
+            blocks.push(Block::UnorderedList(items));
+        }
+    }
+    blocks
+}
+
+/// an atom feed with an entry for every change, `link` gives the url of
+/// a section by its title when the docs are published somewhere
+pub(crate) fn atom(
+    meta: &Meta,
+    changes: &[Change],
+    updated: i64,
+    link: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    let project = slugify(&meta.title);
+    let updated = timestamp(updated);
+    let mut feed = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>{} documentation changes</title>
+  <id>urn:sych:{project}</id>
+  <updated>{updated}</updated>
+"#,
+        escape(&meta.title)
+    );
+    if !meta.authors.is_empty() {
+        feed.push_str(&format!(
+            "  <author><name>{}</name></author>\n",
+            escape(&meta.authors.join(", "))
+        ));
+    }
+    for change in changes {
+        let (kind, content) = match change.kind {
+            ChangeKind::Added => ("Added", "The section was added.".to_owned()),
+            ChangeKind::Removed => ("Removed", "The section was removed.".to_owned()),
+            ChangeKind::Modified => ("Modified", change.diff.join("\n")),
+        };
+        // the same change keeps its id from one build to the next
+        let mut id = vec![change.title.as_str(), kind];
+        id.extend(change.diff.iter().map(String::as_str));
+        feed.push_str(&format!(
+            "  <entry>\n    <title>{kind}: {}</title>\n    <id>urn:sych:{project}:{:016x}</id>\n    <updated>{updated}</updated>\n",
+            escape(&change.title),
+            fnv1a(&id)
+        ));
+        if let Some(href) = link(&change.title) {
+            feed.push_str(&format!("    <link href=\"{}\"/>\n", escape(&href)));
+        }
+        feed.push_str(&format!(
+            "    <content type=\"text\">{}</content>\n  </entry>\n",
+            escape(&content)
+        ));
+    }
+    feed.push_str("</feed>\n");
+    feed
+}
+
+/// 64-bit FNV-1a of the parts, each followed by a zero byte, unlike the
+/// hasher of std it gives the same value with every Rust release
+fn fnv1a(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in parts.iter().flat_map(|part| part.bytes().chain([0])) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gives_entries_ids_which_do_not_depend_on_the_rust_release() {
+        assert_eq!(fnv1a(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_ne!(fnv1a(&["ab", "c"]), fnv1a(&["a", "bc"]));
+
+        let meta = Meta {
+            title: "Docs".to_owned(),
+            ..Meta::default()
+        };
+        let change = Change {
+            title: "Retries".to_owned(),
+            kind: ChangeKind::Modified,
+            diff: vec!["-three".to_owned(), "+five".to_owned()],
+        };
+        let feed = atom(&meta, &[change], 0, &|_| None);
+        assert!(
+            feed.contains("<id>urn:sych:docs:f7a0669886e8e270</id>"),
+            "{feed}"
+        );
+    }
+}
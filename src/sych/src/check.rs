@@ -0,0 +1,446 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use markdown::{Block, ListItem, Span};
+use serde::Serialize;
+
+use crate::anchor::{resolve_path, Anchors, Link};
+use crate::external::{check_links, ExternalLink};
+use crate::include::BrokenSnippet;
+use crate::indexer::{Collision, Section, SectionPart};
+use crate::{ExtensionMeta, MergeStrategy, SychConfig};
+
+/// languages which are highlighted as plain code blocks, any other fence
+/// is expected to be one of the configured extensions
+#[rustfmt::skip]
+const KNOWN_LANGUAGES: &[&str] = &[
+    "bash", "c", "console", "cpp", "cs", "css", "csv", "diff", "dockerfile", "dot", "go", "graphql",
+    "html", "ini", "java", "javascript", "js", "json", "jsx", "kotlin", "lua", "makefile",
+    "markdown", "md", "php", "proto", "python", "py", "rb", "ruby", "rust", "rs", "scala", "scss",
+    "sh", "shell", "sql", "swift", "text", "toml", "ts", "tsx", "txt", "typescript", "xml", "yaml",
+    "yml", "zsh",
+];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// a single problem found in the docs
+#[derive(Serialize, Debug)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// short, stable name of the check, e.g. "broken-link"
+    pub code: &'static str,
+    pub file: String,
+    /// 1-based line inside `file`, when it could be found
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "{}:{}: ", self.file, line)?,
+            None => write!(f, "{}: ", self.file)?,
+        }
+        write!(f, "{}[{}]: {}", severity, self.code, self.message)
+    }
+}
+
+/// everything the checks need to know about the indexed docs
+pub(crate) struct CheckInput<'a> {
+    pub sych_cfg: &'a SychConfig,
+    pub root: &'a Path,
+    /// directory of sych.toml, `covers` paths are relative to it
+    pub base: &'a Path,
+    pub docs_index: &'a IndexMap<String, Section>,
+    pub collisions: &'a [Collision],
+    /// markdown files which were left out by `[doc] exclude`
+    pub excluded: &'a [String],
+    pub broken_snippets: &'a [BrokenSnippet],
+    /// requests every http(s) link when set, results are cached inside it
+    pub external_cache: Option<&'a Path>,
+}
+
+/// runs every check against the indexed docs, nothing is written to disk
+pub(crate) fn check(input: &CheckInput) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        input,
+        anchors: Anchors::build(input.docs_index),
+        sources: HashMap::new(),
+        found: HashMap::new(),
+        covers_checked: HashSet::new(),
+        external: vec![],
+        diagnostics: vec![],
+    };
+
+    for collision in input.collisions {
+        let severity = match collision.strategy {
+            MergeStrategy::Error => Severity::Error,
+            _ => Severity::Warning,
+        };
+        for source in &collision.sources {
+            let line = checker.find_heading(source, &collision.title);
+            checker.report(
+                severity,
+                "duplicate-title",
+                source,
+                line,
+                format!(
+                    "section \"{}\" is also declared in {}",
+                    collision.title,
+                    collision
+                        .sources
+                        .iter()
+                        .filter(|s| *s != source)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            );
+        }
+    }
+
+    for snippet in input.broken_snippets {
+        checker.report(
+            Severity::Error,
+            "missing-snippet",
+            &snippet.source,
+            snippet.span.as_ref().map(|span| span.line),
+            snippet.message.clone(),
+        );
+    }
+
+    for (title, section) in input.docs_index {
+        if section.parts.iter().all(|part| part.blocks.is_empty()) {
+            let source = &section.parts[0].source;
+            let line = checker.find_heading(source, &section.parts[0].title);
+            checker.report(
+                Severity::Warning,
+                "empty-section",
+                source,
+                line,
+                format!("section \"{title}\" has no content"),
+            );
+        }
+
+        for part in &section.parts {
+            checker.check_blocks(&part.source, &part.blocks);
+            checker.check_covers(title, part);
+        }
+    }
+
+    if let Some(cache_dir) = input.external_cache {
+        checker.check_external(cache_dir);
+    }
+
+    checker.diagnostics
+}
+
+struct Checker<'a> {
+    input: &'a CheckInput<'a>,
+    anchors: Anchors,
+    /// raw markdown of every file, read lazily for finding line numbers
+    sources: HashMap<String, String>,
+    /// (file, text) -> how many times the text was already looked up,
+    /// repeated links and fences are found in the order they are written
+    found: HashMap<(String, String), usize>,
+    /// files whose `covers` were already checked
+    covers_checked: HashSet<String>,
+    /// http(s) links collected while walking the docs
+    external: Vec<ExternalLink>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn report(
+        &mut self,
+        severity: Severity,
+        code: &'static str,
+        source: &str,
+        line: Option<usize>,
+        message: String,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            code,
+            file: self.input.root.join(source).to_string_lossy().into_owned(),
+            line,
+            message,
+        });
+    }
+
+    fn content(&mut self, source: &str) -> &str {
+        let root = self.input.root;
+        self.sources
+            .entry(source.to_owned())
+            .or_insert_with(|| std::fs::read_to_string(root.join(source)).unwrap_or_default())
+    }
+
+    /// the markdown parser does not keep positions, so we look for the
+    /// line of the `##` heading with the given title
+    fn find_heading(&mut self, source: &str, title: &str) -> Option<usize> {
+        let heading = format!("## {title}");
+        self.content(source)
+            .lines()
+            .position(|line| line.trim_end().trim_end_matches('#').trim_end() == heading)
+            .map(|i| i + 1)
+    }
+
+    /// the line of the next occurrence of `needle` which is not part of a
+    /// longer link or path, every call for the same text moves on to the
+    /// next one as the blocks are walked in the order they are written
+    fn find_next_line(&mut self, source: &str, needle: &str) -> Option<usize> {
+        let key = (source.to_owned(), needle.to_owned());
+        let skip = self.found.get(&key).copied().unwrap_or_default();
+        let mut seen = 0;
+        let mut found = None;
+        for (i, line) in self.content(source).lines().enumerate() {
+            seen += occurrences(line, needle);
+            if seen > skip {
+                found = Some(i + 1);
+                break;
+            }
+        }
+        if found.is_some() {
+            self.found.insert(key, skip + 1);
+        }
+        found
+    }
+
+    /// covered paths which do not exist and code which changed too much
+    /// since the file describing it was last committed
+    fn check_covers(&mut self, title: &str, part: &SectionPart) {
+        // every section of a file shares its front matter
+        let covers = match self.covers_checked.insert(part.source.clone()) {
+            true => part.front_matter.covers.as_deref().unwrap_or_default(),
+            false => &[],
+        };
+        for path in covers {
+            if !self.input.base.join(path).exists() {
+                let line = self.find_next_line(&part.source, path);
+                self.report(
+                    Severity::Warning,
+                    "missing-cover",
+                    &part.source,
+                    line,
+                    format!("covers {path} which does not exist"),
+                );
+            }
+        }
+
+        if let Some(stale) = &part.stale {
+            let line = self.find_heading(&part.source, &part.title);
+            self.report(
+                Severity::Warning,
+                "stale-note",
+                &part.source,
+                line,
+                format!(
+                    "section \"{title}\" is possibly outdated, {} changed by {} lines in {} since the file was last committed",
+                    stale.paths.join(", "),
+                    stale.lines,
+                    stale.commits_label()
+                ),
+            );
+        }
+    }
+
+    fn check_blocks(&mut self, source: &str, blocks: &[Block]) {
+        for block in blocks {
+            match block {
+                Block::Header(spans, _) | Block::Paragraph(spans) => {
+                    self.check_spans(source, spans);
+                }
+                Block::Blockquote(bq) => self.check_blocks(source, bq),
+                Block::OrderedList(items, _) | Block::UnorderedList(items) => {
+                    for item in items {
+                        match item {
+                            ListItem::Simple(spans) => self.check_spans(source, spans),
+                            ListItem::Paragraph(p) => self.check_blocks(source, p),
+                        }
+                    }
+                }
+                Block::CodeBlock(Some(meta), code) => self.check_fence(source, meta, code),
+                _ => continue,
+            }
+        }
+    }
+
+    fn check_spans(&mut self, source: &str, spans: &[Span]) {
+        for span in spans {
+            match span {
+                Span::Link(text, href, _) => self.check_link(source, text, href),
+                Span::Image(_, src, _) => self.check_image(source, src),
+                Span::Emphasis(inner) | Span::Strong(inner) => self.check_spans(source, inner),
+                _ => continue,
+            }
+        }
+    }
+
+    fn check_link(&mut self, source: &str, text: &str, href: &str) {
+        // looked up for every link so that repeated ones keep their lines
+        let line = self.find_next_line(source, &format!("]({href}"));
+        let link = self.anchors.resolve(source, href);
+        if link == Link::External && (href.starts_with("http://") || href.starts_with("https://")) {
+            self.external.push(ExternalLink {
+                url: href.to_owned(),
+                source: source.to_owned(),
+                line,
+            });
+            return;
+        }
+        if !matches!(link, Link::Broken | Link::MissingAnchor) {
+            return;
+        }
+
+        let path = href.split('#').next().unwrap_or_default();
+        let target = resolve_path(source, path).unwrap_or_else(|| path.to_owned());
+        let (code, message) = if link == Link::MissingAnchor {
+            (
+                "missing-anchor",
+                format!("link \"{text}\" points to a heading which does not exist: {href}"),
+            )
+        } else if self.input.excluded.contains(&target) {
+            (
+                "excluded-reference",
+                format!("link \"{text}\" points to {target}, which is excluded from the docs"),
+            )
+        } else {
+            (
+                "broken-link",
+                format!("link \"{text}\" points to a file which is not indexed: {href}"),
+            )
+        };
+        self.report(Severity::Error, code, source, line, message);
+    }
+
+    fn check_image(&mut self, source: &str, src: &str) {
+        let line = self.find_next_line(source, &format!("]({src}"));
+        if self.anchors.resolve(source, src) == Link::External {
+            return;
+        }
+
+        let path = src.split(['#', '?']).next().unwrap_or_default();
+        let exists = match resolve_path(source, path) {
+            Some(target) => self.input.root.join(target).exists(),
+            None => Path::new(source)
+                .parent()
+                .map(|dir| self.input.root.join(dir).join(path).exists())
+                .unwrap_or(false),
+        };
+        if !exists {
+            self.report(
+                Severity::Error,
+                "missing-image",
+                source,
+                line,
+                format!("image {src} does not exist"),
+            );
+        }
+    }
+
+    fn check_external(&mut self, cache_dir: &Path) {
+        let default_cfg = Default::default();
+        let cfg = self
+            .input
+            .sych_cfg
+            .check
+            .as_ref()
+            .and_then(|check| check.external.as_ref())
+            .unwrap_or(&default_cfg);
+
+        let statuses = check_links(cfg, cache_dir, &self.external);
+        for link in std::mem::take(&mut self.external) {
+            let Some(status) = statuses.get(&link.url) else {
+                continue;
+            };
+            if status.is_ok() {
+                continue;
+            }
+            let reason = match (status.status, status.error.as_ref()) {
+                (Some(code), _) => format!("responded with {code}"),
+                (None, Some(error)) => error.to_owned(),
+                (None, None) => "could not be requested".to_owned(),
+            };
+            self.report(
+                Severity::Error,
+                "broken-external-link",
+                &link.source,
+                link.line,
+                format!("{} {}", link.url, reason),
+            );
+        }
+    }
+
+    fn check_fence(&mut self, source: &str, meta: &str, code: &str) {
+        let lang = meta.split_whitespace().next().unwrap_or_default();
+        if lang.is_empty() || KNOWN_LANGUAGES.contains(&lang.to_lowercase().as_str()) {
+            return;
+        }
+
+        let line = self.find_next_line(source, &format!("```{meta}"));
+        let extensions = self.input.sych_cfg.extensions.as_ref();
+        let Some(extension) = extensions.and_then(|e| e.get(lang)) else {
+            self.report(
+                Severity::Warning,
+                "unknown-extension",
+                source,
+                line,
+                format!(
+                    "code fence \"{lang}\" is neither a known language nor a configured extension"
+                ),
+            );
+            return;
+        };
+
+        if let Some(problem) = payload_problem(extension, code) {
+            self.report(
+                Severity::Error,
+                "extension-payload",
+                source,
+                line,
+                format!("payload of extension \"{lang}\" cannot be parsed, {problem}"),
+            );
+        }
+    }
+}
+
+/// times `needle` appears in `line` without being followed by more of a
+/// link or a path, "](b.md" is not found in "](b.md#setup)"
+fn occurrences(line: &str, needle: &str) -> usize {
+    line.match_indices(needle)
+        .filter(|(i, _)| {
+            line[i + needle.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c.is_whitespace() || ")\"',]".contains(c))
+        })
+        .count()
+}
+
+/// why the content of a code fence cannot be handed to its extension,
+/// builds fail for the same reasons
+pub(crate) fn payload_problem(extension: &ExtensionMeta, code: &str) -> Option<String> {
+    // the payload ends up inside a javascript template literal
+    if code.contains('`') || code.contains("${") {
+        return Some("it contains ` or ${ which break the extension script".to_owned());
+    }
+    if extension.payload.as_deref() == Some("json") {
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(code) {
+            return Some(format!("it is not valid json: {e}"));
+        }
+    }
+    None
+}
@@ -0,0 +1,695 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as Schema;
+use toml::{Table, Value};
+use toml_edit::{ImDocument, Item, TableLike};
+
+use crate::error::{report, Level, SourceSpan};
+use crate::external::{
+    DEFAULT_CACHE_TTL_HOURS, DEFAULT_CONCURRENCY, DEFAULT_RATE_LIMIT, DEFAULT_TIMEOUT_SECS,
+};
+use crate::{CheckConfig, Doc, Error, ExternalCheckConfig, Result, SychConfig};
+
+/// the published schema of sych.toml, it is also what unknown keys are
+/// looked up in so that the two cannot drift apart, it lives inside the
+/// crate so that it is packaged with it, schemas/ links to it
+static CONFIG_SCHEMA: &str = include_str!("../schemas/sych.schema.json");
+
+/// overrides of sych.toml which are not meant to be committed
+pub const SYCH_LOCAL_TOML: &str = "sych.local.toml";
+/// prefix of the environment variables overriding the config, nested
+/// keys are separated by a double underscore, e.g. SYCH_DOC__TOC_DEPTH
+const ENV_PREFIX: &str = "SYCH_";
+
+/// an unknown key in the configuration, pointing at the offending text
+/// when it comes from a file
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ConfigDiagnostic {
+    message: String,
+    span: Option<SourceSpan>,
+}
+
+impl ConfigDiagnostic {
+    fn new(file: &str, content: &str, range: Range<usize>, message: String) -> Self {
+        ConfigDiagnostic {
+            message,
+            span: Some(SourceSpan::new(file, content, range)),
+        }
+    }
+
+    pub fn span(&self) -> Option<&SourceSpan> {
+        self.span.as_ref()
+    }
+
+    /// the warning along with the line it points at, colored with ANSI
+    /// escapes when `color` is set
+    pub fn report(&self, color: bool) -> String {
+        report(Level::Warning, &self.message, self.span.as_ref(), color)
+    }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = self.span.as_ref() {
+            write!(f, "{}:{}:{}: ", span.file, span.line, span.column)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// where an effective value of the configuration came from
+#[derive(Debug, Clone)]
+pub(crate) enum Origin {
+    Default,
+    File { path: String, line: usize },
+    Env(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File { path, line } => write!(f, "{path}:{line}"),
+            Origin::Env(name) => write!(f, "env {name}"),
+        }
+    }
+}
+
+/// the configuration merged from every layer
+pub(crate) struct LoadedConfig {
+    pub sych_cfg: SychConfig,
+    /// unknown keys of every layer
+    pub warnings: Vec<ConfigDiagnostic>,
+    /// dotted key -> layer which set it, keys missing here are defaults
+    origins: BTreeMap<String, Origin>,
+}
+
+/// loads the configuration, every layer overrides the ones before it:
+///
+/// 1. the user config, `~/.config/sych/config.toml`
+/// 2. sych.toml of the project
+/// 3. sych.local.toml next to it
+/// 4. SYCH_* environment variables
+///
+/// tables are merged key by key, anything else (including lists) is
+/// replaced as a whole
+pub(crate) fn load_config(config_path: &Path) -> Result<LoadedConfig> {
+    let mut files = vec![];
+    if let Some(user_config) = user_config_path().filter(|path| path.is_file()) {
+        // shown in full as it is not next to sych.toml
+        files.push((user_config.to_string_lossy().into_owned(), user_config));
+    }
+    files.push((SYCH_TOML_NAME.to_owned(), config_path.to_path_buf()));
+    let local = config_path.with_file_name(SYCH_LOCAL_TOML);
+    if local.is_file() {
+        files.push((SYCH_LOCAL_TOML.to_owned(), local));
+    }
+    load_layers(config_path, &files, std::env::vars().collect())
+}
+
+/// merges the given files, as (name shown, path), and then the SYCH_*
+/// variables among `env`, in the order of their names
+fn load_layers(
+    config_path: &Path,
+    files: &[(String, PathBuf)],
+    mut env: Vec<(String, String)>,
+) -> Result<LoadedConfig> {
+    let schema: Schema = serde_json::from_str(CONFIG_SCHEMA).map_err(Error::render)?;
+    let mut merged = Table::new();
+    let mut origins = BTreeMap::new();
+    let mut warnings = vec![];
+
+    for (file, path) in files {
+        let content = std::fs::read_to_string(path).map_err(Error::io(path))?;
+        let (layer, lines) = read_layer(file, &content, &schema, &mut warnings)?;
+        merge(&mut merged, layer, "", &mut origins, &|key| Origin::File {
+            path: file.clone(),
+            line: lines.get(key).copied().unwrap_or(1),
+        });
+    }
+
+    env.sort();
+    for (name, raw) in env {
+        let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let segments: Vec<String> = key.split("__").map(str::to_lowercase).collect();
+        let Ok(target) = key_schema(&schema, &segments) else {
+            warnings.push(ConfigDiagnostic {
+                message: format!(
+                    "{name} does not match any key of sych.toml, it is ignored (nested keys are separated by __)"
+                ),
+                span: None,
+            });
+            continue;
+        };
+
+        let mut layer = Table::new();
+        let mut table = &mut layer;
+        let (last, parents) = segments.split_last().expect("split yields a segment");
+        for segment in parents {
+            table = table
+                .entry(segment.clone())
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .expect("only tables are inserted");
+        }
+        table.insert(last.clone(), env_value(&raw, target));
+        merge(&mut merged, layer, "", &mut origins, &|_| {
+            Origin::Env(name.clone())
+        });
+        // checked one by one so that the error names the variable
+        if let Err(e) = Value::Table(merged.clone()).try_into::<SychConfig>() {
+            return Err(Error::config(format!(
+                "invalid value of {name}, {}",
+                e.message().trim_end()
+            )));
+        }
+    }
+
+    let mut sych_cfg: SychConfig =
+        Value::Table(merged)
+            .try_into()
+            .map_err(|e: toml::de::Error| {
+                Error::config(format!("invalid configuration, {}", e.message().trim_end()))
+            })?;
+
+    if sych_cfg.meta.title.is_empty() {
+        sych_cfg.meta.title = config_path
+            .canonicalize()
+            .ok()
+            .and_then(|path| {
+                path.parent()?
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "docs".to_owned());
+    }
+
+    Ok(LoadedConfig {
+        sych_cfg,
+        warnings,
+        origins,
+    })
+}
+
+const SYCH_TOML_NAME: &str = "sych.toml";
+/// the origins of `config show --origin` are aligned up to this column
+const MAX_ORIGIN_COLUMN: usize = 60;
+
+/// parses a single config file, returns its values along with the line
+/// of every key
+fn read_layer(
+    file: &str,
+    content: &str,
+    schema: &Schema,
+    warnings: &mut Vec<ConfigDiagnostic>,
+) -> Result<(Table, BTreeMap<String, usize>)> {
+    // values are checked on their own so that errors point at the file
+    // they are in, a layer only overriding a few keys may be incomplete
+    if let Err(e) = toml::from_str::<SychConfig>(content) {
+        let message = e.message().trim_end().to_owned();
+        if !message.starts_with("missing field") {
+            return Err(toml_error(file, content, message, e.span()));
+        }
+    }
+
+    let layer = toml::from_str::<Table>(content)
+        .map_err(|e| toml_error(file, content, e.message().to_owned(), e.span()))?;
+    let document = ImDocument::parse(content)
+        .map_err(|e| toml_error(file, content, e.message().to_owned(), e.span()))?;
+    let mut lines = BTreeMap::new();
+    let mut layer_warnings = vec![];
+    let mut walker = KeyWalker {
+        file,
+        content,
+        lines: &mut lines,
+        warnings: &mut layer_warnings,
+    };
+    walker.walk(document.as_table(), Some(schema), "");
+    layer_warnings.sort_by_key(|w| w.span.as_ref().map(|s| (s.line, s.column)));
+    warnings.extend(layer_warnings);
+    Ok((layer, lines))
+}
+
+fn toml_error(file: &str, content: &str, message: String, range: Option<Range<usize>>) -> Error {
+    Error::Config {
+        message: message.trim_end().to_owned(),
+        span: range.map(|range| SourceSpan::new(file, content, range)),
+    }
+}
+
+/// walks a config file along the schema, records the line of every key
+/// and reports every key the schema has no place for
+struct KeyWalker<'a> {
+    file: &'a str,
+    content: &'a str,
+    lines: &'a mut BTreeMap<String, usize>,
+    warnings: &'a mut Vec<ConfigDiagnostic>,
+}
+
+impl KeyWalker<'_> {
+    fn walk(&mut self, table: &dyn TableLike, schema: Option<&Schema>, path: &str) {
+        for (name, item) in table.iter() {
+            let key_path = join_key(path, name);
+            let span = table
+                .key(name)
+                .and_then(|key| key.span())
+                .or_else(|| find_key(self.content, path, name))
+                .unwrap_or(0..0);
+            self.lines
+                .insert(key_path.clone(), line_of(self.content, span.start));
+
+            let child = match schema.map(|schema| child_schema(schema, name)) {
+                Some(Ok(child)) => child,
+                Some(Err(known)) => {
+                    let mut message = format!("unknown key `{key_path}`");
+                    if let Some(suggestion) = did_you_mean(name, &known) {
+                        message.push_str(&format!(", did you mean `{suggestion}`?"));
+                    } else {
+                        message.push_str(", it is ignored");
+                    }
+                    self.warnings.push(ConfigDiagnostic::new(
+                        self.file,
+                        self.content,
+                        span,
+                        message,
+                    ));
+                    continue;
+                }
+                None => None,
+            };
+
+            match item {
+                Item::Table(t) => self.walk(t, child, &key_path),
+                Item::Value(toml_edit::Value::InlineTable(t)) => self.walk(t, child, &key_path),
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// schema of a key inside a table, `Ok(None)` when anything goes and
+/// the known keys when the key is not allowed
+fn child_schema<'a>(schema: &'a Schema, name: &str) -> Result<Option<&'a Schema>, Vec<&'a str>> {
+    let properties = schema["properties"].as_object();
+    if let Some(child) = properties.and_then(|p| p.get(name)) {
+        return Ok(Some(child));
+    }
+    match &schema["additionalProperties"] {
+        additional if additional.is_object() => Ok(Some(additional)),
+        Schema::Bool(false) => Err(properties
+            .map(|p| p.keys().map(String::as_str).collect())
+            .unwrap_or_default()),
+        _ => Ok(None),
+    }
+}
+
+/// schema of a nested key, like `child_schema` but following every
+/// segment
+fn key_schema<'a>(
+    schema: &'a Schema,
+    segments: &[String],
+) -> Result<Option<&'a Schema>, Vec<&'a str>> {
+    let mut current = Some(schema);
+    for segment in segments {
+        current = match current {
+            Some(schema) => child_schema(schema, segment)?,
+            None => None,
+        };
+    }
+    Ok(current)
+}
+
+/// environment values are read as toml when the key holds a number, a
+/// boolean or a list, e.g. `3`, `true` or `["a.md"]`, anything else is
+/// kept as it is so that `SYCH_META__VERSION=1.0` stays a string
+fn env_value(raw: &str, schema: Option<&Schema>) -> Value {
+    let typed = schema.is_none_or(|schema| {
+        matches!(
+            schema["type"].as_str(),
+            Some("integer" | "number" | "boolean" | "array")
+        )
+    });
+    if !typed {
+        return Value::String(raw.to_owned());
+    }
+    toml::from_str::<Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_owned()))
+}
+
+/// merges `layer` into `base`, recording the origin of every value set
+fn merge(
+    base: &mut Table,
+    layer: Table,
+    path: &str,
+    origins: &mut BTreeMap<String, Origin>,
+    origin: &dyn Fn(&str) -> Origin,
+) {
+    for (name, value) in layer {
+        let key_path = join_key(path, &name);
+        match value {
+            Value::Table(table) => {
+                let entry = base
+                    .entry(name)
+                    .or_insert_with(|| Value::Table(Table::new()));
+                if !entry.is_table() {
+                    *entry = Value::Table(Table::new());
+                }
+                let entry = entry.as_table_mut().expect("replaced by a table above");
+                merge(entry, table, &key_path, origins, origin);
+            }
+            value => {
+                origins.insert(key_path.clone(), origin(&key_path));
+                base.insert(name, value);
+            }
+        }
+    }
+}
+
+fn join_key(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(config_home.join("sych").join("config.toml"))
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// implicit tables, e.g. `doc` in `[doc.sections.x]`, have no span,
+/// they are looked up as a header below `path` or as a dotted key at
+/// the start of a line of the table `path`
+fn find_key(content: &str, path: &str, name: &str) -> Option<Range<usize>> {
+    let key_path = join_key(path, name);
+    let mut table = String::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset + line.len() - line.trim_start().len();
+        offset += line.len();
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.trim_start_matches('[');
+            let header_start = start + line.len() - header.len();
+            table = header
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_owned();
+            let nested = table
+                .strip_prefix(&key_path)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'));
+            if nested && header.starts_with(&key_path) {
+                let name_start = header_start + key_path.len() - name.len();
+                return Some(name_start..name_start + name.len());
+            }
+            continue;
+        }
+        let is_key = line
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.trim_start().starts_with(['=', '.']));
+        if table == path && is_key {
+            return Some(start..start + name.len());
+        }
+    }
+    None
+}
+
+/// the closest known key, when it is close enough to be a typo
+fn did_you_mean<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|k| (strsim::damerau_levenshtein(name, k), *k))
+        .filter(|(distance, k)| *distance <= (k.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, k)| k)
+}
+
+impl LoadedConfig {
+    /// a config built in code, every value it does not set is a default
+    pub fn from_config(sych_cfg: SychConfig) -> Self {
+        LoadedConfig {
+            sych_cfg,
+            warnings: vec![],
+            origins: BTreeMap::new(),
+        }
+    }
+
+    /// the configuration with every default filled in, as used by sych
+    pub fn effective(&self) -> SychConfig {
+        let mut effective = self.sych_cfg.clone();
+
+        let doc = effective.doc.get_or_insert_with(Doc::default);
+        doc.root.get_or_insert_with(|| ".".to_owned());
+        doc.exclude.get_or_insert_with(Vec::new);
+        doc.merge.get_or_insert_with(Default::default);
+        doc.toc_depth.get_or_insert(self.sych_cfg.toc_depth());
+
+        let check = effective.check.get_or_insert_with(CheckConfig::default);
+        let external = check
+            .external
+            .get_or_insert_with(ExternalCheckConfig::default);
+        external.concurrency.get_or_insert(DEFAULT_CONCURRENCY);
+        external.timeout.get_or_insert(DEFAULT_TIMEOUT_SECS);
+        external.rate_limit.get_or_insert(DEFAULT_RATE_LIMIT);
+        external.cache_ttl.get_or_insert(DEFAULT_CACHE_TTL_HOURS);
+
+        effective
+    }
+
+    /// every effective value as `key = value`, followed by the layer it
+    /// came from
+    pub fn show_origins(&self) -> Result<String> {
+        let effective = Value::try_from(self.effective()).map_err(Error::render)?;
+        let Value::Table(effective) = effective else {
+            return Ok(String::new());
+        };
+        let mut leaves = vec![];
+        flatten(&effective, "", "", &mut leaves);
+
+        let lines: Vec<(String, String)> = leaves
+            .into_iter()
+            .map(|(path, key, value)| {
+                let origin = self.origins.get(&path).cloned().unwrap_or(Origin::Default);
+                (format!("{key} = {value}"), origin.to_string())
+            })
+            .collect();
+        // long values such as refs are not taken into account
+        let width = lines
+            .iter()
+            .map(|(line, _)| line.chars().count())
+            .filter(|width| *width <= MAX_ORIGIN_COLUMN)
+            .max()
+            .unwrap_or_default();
+        let mut out = String::new();
+        for (line, origin) in lines {
+            out.push_str(&format!("{line:<width$}  # {origin}\n"));
+        }
+        Ok(out)
+    }
+}
+
+/// every value which is not a table as (path, dotted toml key, value)
+fn flatten(table: &Table, path: &str, key: &str, leaves: &mut Vec<(String, String, String)>) {
+    for (name, value) in table {
+        // keys such as section titles have to be quoted
+        let bare = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        let name_key = if bare {
+            name.clone()
+        } else {
+            format!("{name:?}")
+        };
+        let value_path = join_key(path, name);
+        let value_key = join_key(key, &name_key);
+        match value {
+            Value::Table(table) => flatten(table, &value_path, &value_key, leaves),
+            value => leaves.push((value_path, value_key, value.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        serde_json::from_str(CONFIG_SCHEMA).unwrap()
+    }
+
+    #[test]
+    fn finds_implicit_tables_inside_their_table() {
+        let content = "# the doc table\n\
+                       [meta]\n\
+                       title = \"doc\"\n\n\
+                       [doc.sections.intro]\n\
+                       merge = \"separate\"\n\n\
+                       [check]\n\
+                       external.timeout = 3\n";
+        let found = |path, name| find_key(content, path, name).map(|range| &content[range.clone()]);
+        let line =
+            |path, name| find_key(content, path, name).map(|range| line_of(content, range.start));
+
+        assert_eq!(found("", "doc"), Some("doc"));
+        assert_eq!(line("", "doc"), Some(5));
+        assert_eq!(found("doc", "sections"), Some("sections"));
+        assert_eq!(line("doc", "sections"), Some(5));
+        assert_eq!(line("check", "external"), Some(9));
+        // neither in another table nor inside a value or a comment
+        assert_eq!(line("", "title"), None);
+        assert_eq!(line("meta", "doc"), None);
+        assert_eq!(line("doc", "external"), None);
+    }
+
+    #[test]
+    fn suggests_close_keys_only() {
+        let known = ["title", "version", "authors", "description"];
+        assert_eq!(did_you_mean("tittle", &known), Some("title"));
+        assert_eq!(did_you_mean("verison", &known), Some("version"));
+        assert_eq!(did_you_mean("author", &known), Some("authors"));
+        assert_eq!(did_you_mean("name", &known), None);
+        assert_eq!(did_you_mean("x", &["y"]), Some("y"));
+    }
+
+    #[test]
+    fn warns_about_unknown_keys_on_their_line() {
+        let content = "[meta]\n\
+                       title = \"t\"\n\
+                       tittle = \"t\"\n\n\
+                       [doc]\n\
+                       unknown_thing = 1\n\n\
+                       [docs.sections.intro]\n\
+                       merge = \"separate\"\n";
+        let mut warnings = vec![];
+        let (_, lines) = read_layer("sych.toml", content, &schema(), &mut warnings).unwrap();
+        let warnings: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            [
+                "sych.toml:3:1: unknown key `meta.tittle`, did you mean `title`?",
+                "sych.toml:6:1: unknown key `doc.unknown_thing`, it is ignored",
+                "sych.toml:8:2: unknown key `docs`, did you mean `doc`?",
+            ]
+        );
+        assert_eq!(lines["meta.title"], 2);
+        assert_eq!(lines["docs"], 8);
+    }
+
+    fn layers(name: &str, env: &[(&str, &str)]) -> Result<LoadedConfig> {
+        let dir = std::env::temp_dir().join(format!("sych-config-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("sych.toml"),
+            "[meta]\ntitle = \"Docs\"\n\n[doc]\ntoc_depth = 1\nroot = \"docs\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join(SYCH_LOCAL_TOML), "[doc]\ntoc_depth = 2\n").unwrap();
+        let files = [
+            (SYCH_TOML_NAME.to_owned(), dir.join("sych.toml")),
+            (SYCH_LOCAL_TOML.to_owned(), dir.join(SYCH_LOCAL_TOML)),
+        ];
+        let env = env
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        load_layers(&dir.join("sych.toml"), &files, env)
+    }
+
+    #[test]
+    fn layers_override_each_other_and_keep_their_origin() {
+        let loaded = layers(
+            "origins",
+            &[
+                ("SYCH_CHECK__EXTERNAL__TIMEOUT", "30"),
+                ("HOME", "/home/me"),
+                ("SYCH_DOC__GIT", "false"),
+            ],
+        )
+        .unwrap();
+        let doc = loaded.sych_cfg.doc.as_ref().unwrap();
+        assert_eq!(doc.toc_depth, Some(2));
+        assert_eq!(doc.root.as_deref(), Some("docs"));
+        assert_eq!(doc.git, Some(false));
+        let timeout = loaded.sych_cfg.check.as_ref().unwrap().external.as_ref();
+        assert_eq!(timeout.unwrap().timeout, Some(30));
+
+        let origins = loaded.show_origins().unwrap();
+        let origin = |key: &str| {
+            origins
+                .lines()
+                .find(|line| line.starts_with(&format!("{key} =")))
+                .and_then(|line| line.split("# ").nth(1))
+                .unwrap()
+                .to_owned()
+        };
+        assert_eq!(origin("meta.title"), "sych.toml:2");
+        assert_eq!(origin("doc.root"), "sych.toml:6");
+        assert_eq!(origin("doc.toc_depth"), "sych.local.toml:2");
+        assert_eq!(origin("doc.git"), "env SYCH_DOC__GIT");
+        assert_eq!(
+            origin("check.external.timeout"),
+            "env SYCH_CHECK__EXTERNAL__TIMEOUT"
+        );
+        assert_eq!(origin("check.external.concurrency"), "default");
+    }
+
+    #[test]
+    fn keeps_env_values_of_strings_as_written() {
+        let loaded = layers(
+            "strings",
+            &[
+                ("SYCH_META__VERSION", "1.0"),
+                ("SYCH_META__TITLE", "true"),
+                ("SYCH_DOC__EXCLUDE", "[\"drafts\"]"),
+                ("SYCH_CHECK__EXTERNAL__RATE_LIMIT", "0.5"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(loaded.sych_cfg.meta.version, "1.0");
+        assert_eq!(loaded.sych_cfg.meta.title, "true");
+        let doc = loaded.sych_cfg.doc.as_ref().unwrap();
+        assert_eq!(doc.exclude.as_deref(), Some(&["drafts".to_owned()][..]));
+        let external = loaded.sych_cfg.check.as_ref().unwrap().external.as_ref();
+        assert_eq!(external.unwrap().rate_limit, Some(0.5));
+    }
+
+    #[test]
+    fn applies_env_values_in_the_order_of_their_names() {
+        // both name doc.toc_depth, the lowercase one sorts last
+        let loaded = layers(
+            "order",
+            &[("SYCH_doc__toc_depth", "4"), ("SYCH_DOC__TOC_DEPTH", "3")],
+        )
+        .unwrap();
+        assert_eq!(loaded.sych_cfg.doc.as_ref().unwrap().toc_depth, Some(4));
+
+        let Err(Error::Config { message, .. }) =
+            layers("invalid", &[("SYCH_DOC__TOC_DEPTH", "deep")])
+        else {
+            panic!("a toc depth has to be a number");
+        };
+        assert!(
+            message.starts_with("invalid value of SYCH_DOC__TOC_DEPTH"),
+            "{message}"
+        );
+    }
+}
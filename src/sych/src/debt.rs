@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use markdown::{Block, ListItem, Span};
+
+use crate::git::blame;
+use crate::harvest::relative_to;
+use crate::{DebtConfig, DebtGrouping};
+
+const DEFAULT_TAGS: &[&str] = &["TODO", "FIXME", "HACK"];
+const DEFAULT_TITLE: &str = "Technical debt";
+
+/// a tagged comment, e.g. `// TODO(ana): retry on timeouts`
+#[derive(Debug)]
+pub(crate) struct DebtComment {
+    pub tag: String,
+    /// file as listed in the section, relative to sych.toml
+    pub file: String,
+    pub path: PathBuf,
+    /// 1-based
+    pub line: usize,
+    pub text: String,
+    /// from git blame, or the name in `TODO(name)` when git has none
+    pub author: Option<String>,
+}
+
+/// every tagged comment of the files matching `[debt] paths`, in file
+/// and line order
+pub(crate) fn scan(debt: &DebtConfig, base: &Path, exclude: &[String]) -> Vec<DebtComment> {
+    let tags = tags(debt);
+
+    let mut files: Vec<(String, PathBuf)> = vec![];
+    for pattern in &debt.paths {
+        let pattern = pattern.trim_start_matches("./");
+        for path in walk(&base.join(glob_prefix(pattern))) {
+            let file = relative_to(base, &path).replace('\\', "/");
+            if glob_matches(pattern, &file)
+                && !exclude.iter().any(|search_path| file.contains(search_path))
+                && !files.iter().any(|(seen, _)| *seen == file)
+            {
+                files.push((file, path));
+            }
+        }
+    }
+    files.sort();
+
+    let mut comments = vec![];
+    for (file, path) in files {
+        // binary and non utf-8 files have no comments worth listing
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let found: Vec<(usize, String, Option<String>, String)> = content
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                tagged(line, &tags).map(|(tag, name, text)| (i + 1, tag, name, text))
+            })
+            .collect();
+        if found.is_empty() {
+            continue;
+        }
+
+        let authors = match debt.blame {
+            Some(false) => None,
+            _ => blame(&path),
+        };
+        for (line, tag, name, text) in found {
+            let blamed = authors
+                .as_ref()
+                .and_then(|authors| authors.get(line - 1).cloned().flatten());
+            comments.push(DebtComment {
+                tag,
+                file: file.clone(),
+                path: path.clone(),
+                line,
+                text,
+                author: blamed.or(name),
+            });
+        }
+    }
+    comments
+}
+
+fn tags(debt: &DebtConfig) -> Vec<String> {
+    match &debt.tags {
+        Some(tags) => tags.clone(),
+        None => DEFAULT_TAGS.iter().map(|tag| tag.to_string()).collect(),
+    }
+}
+
+/// tag, name and text of a tagged comment, the tag has to start the
+/// comment, e.g. `// TODO: ...`, `# FIXME(ana) ...` or `/* HACK ... */`
+fn tagged(line: &str, tags: &[String]) -> Option<(String, Option<String>, String)> {
+    let trimmed = line.trim_start();
+    let comment = if trimmed.starts_with('*') {
+        // the middle of a block comment
+        trimmed
+    } else {
+        let start = ["//", "#", "--", "/*", "<!--"]
+            .iter()
+            .filter_map(|marker| line.find(marker))
+            .min()?;
+        &line[start..]
+    };
+    let comment = comment
+        .trim_start_matches(['/', '!', '*', '#', '-', '<'])
+        .trim_start();
+
+    tags.iter().find_map(|tag| {
+        let rest = comment.strip_prefix(tag.as_str())?;
+        let (name, rest) = match rest.strip_prefix('(') {
+            Some(rest) => {
+                let (name, rest) = rest.split_once(')')?;
+                (Some(name.trim().to_owned()), rest)
+            }
+            None if rest.is_empty() || rest.starts_with([':', ' ', '\t']) => (None, rest),
+            None => return None,
+        };
+        let text = rest
+            .trim_start_matches(':')
+            .trim()
+            .trim_end_matches("*/")
+            .trim_end_matches("-->")
+            .trim();
+        Some((tag.clone(), name, text.to_owned()))
+    })
+}
+
+/// the generated section listing the comments grouped as configured,
+/// every comment links to its file and line
+pub(crate) fn debt_blocks(debt: &DebtConfig, comments: &[DebtComment], root: &Path) -> Vec<Block> {
+    let title = debt.title.as_deref().unwrap_or(DEFAULT_TITLE);
+    let mut blocks = vec![Block::Header(vec![Span::Text(title.to_owned())], 2)];
+
+    // tag -> count, in the order the tags are configured
+    let counts: Vec<(String, usize)> = tags(debt)
+        .into_iter()
+        .map(|tag| {
+            let count = comments.iter().filter(|c| c.tag == tag).count();
+            (tag, count)
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    let summary = match comments.len() {
+        0 => "No tagged comments were found.".to_owned(),
+        total => format!(
+            "{total} tagged comments: {}.",
+            counts
+                .iter()
+                .map(|(tag, count)| format!("{count} {tag}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    blocks.push(Block::Paragraph(vec![Span::Text(summary)]));
+
+    let grouping = debt.group_by.unwrap_or_default();
+    let mut groups: BTreeMap<String, Vec<&DebtComment>> = BTreeMap::new();
+    for comment in comments {
+        let key = match grouping {
+            DebtGrouping::File => comment.file.clone(),
+            DebtGrouping::Directory => match comment.file.rsplit_once('/') {
+                Some((dir, _)) => dir.to_owned(),
+                None => ".".to_owned(),
+            },
+            DebtGrouping::Tag => comment.tag.clone(),
+            DebtGrouping::Author => comment
+                .author
+                .clone()
+                .unwrap_or_else(|| "unknown".to_owned()),
+        };
+        groups.entry(key).or_default().push(comment);
+    }
+
+    for (group, comments) in groups {
+        blocks.push(Block::Header(vec![Span::Text(group)], 3));
+        let items = comments
+            .into_iter()
+            .map(|comment| {
+                let href = match &debt.url {
+                    Some(url) => url
+                        .replace("{path}", &comment.file)
+                        .replace("{line}", &comment.line.to_string()),
+                    None => relative_to(root, &comment.path),
+                };
+                let mut spans = vec![
+                    Span::Code(comment.tag.clone()),
+                    Span::Text(" ".to_owned()),
+                    Span::Link(format!("{}:{}", comment.file, comment.line), href, None),
+                ];
+                if !comment.text.is_empty() {
+                    spans.push(Span::Text(format!(" {}", comment.text)));
+                }
+                if let Some(author) = comment
+                    .author
+                    .as_ref()
+                    .filter(|_| grouping != DebtGrouping::Author)
+                {
+                    spans.push(Span::Emphasis(vec![Span::Text(format!(" ({author})"))]));
+                }
+                ListItem::Simple(spans)
+            })
+            .collect();
+        blocks.push(Block::UnorderedList(items));
+    }
+    blocks
+}
+
+/// every file below `dir`, respecting .gitignore
+fn walk(dir: &Path) -> Vec<PathBuf> {
+    if dir.is_file() {
+        return vec![dir.to_path_buf()];
+    }
+    rust_search::SearchBuilder::default()
+        .location(dir)
+        .build()
+        .map(PathBuf::from)
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// directories of a glob before its first wildcard, where the walk starts
+fn glob_prefix(pattern: &str) -> String {
+    pattern
+        .split('/')
+        .take_while(|segment| !segment.contains(['*', '?']))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `*` and `?` match inside a path segment, `**` matches any number of
+/// segments, e.g. `src/**/*.rs`
+pub(crate) fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((segment, rest)) => {
+            !path.is_empty()
+                && segment_matches(segment.as_bytes(), path[0].as_bytes())
+                && segments_match(rest, &path[1..])
+        }
+    }
+}
+
+fn segment_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| segment_matches(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && segment_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && segment_matches(rest, &name[1..]),
+    }
+}
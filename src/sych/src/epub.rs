@@ -0,0 +1,526 @@
+use std::collections::HashMap;
+use std::io::{Seek, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use indexmap::IndexMap;
+use markdown::{Block, ListItem, Span};
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+use zip::result::ZipResult;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::anchor::{resolve_path, spans_to_text, Anchors, Link, Slugger};
+use crate::indexer::Section;
+use crate::{Error, Result, SychConfig};
+
+/// theme of the highlighted code, e-readers are mostly light
+const CODE_THEME: &str = "InspiredGitHub";
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+const EPUB_CSS: &str = r#"body { font-family: serif; line-height: 1.5; }
+h1, h2, h3, h4, h5 { font-family: sans-serif; }
+pre { font-size: 0.8em; white-space: pre-wrap; padding: 0.5em; background: #f6f8fa; }
+code { font-size: 0.9em; }
+blockquote { margin-left: 1em; padding-left: 1em; border-left: 3px solid #ccc; }
+img { max-width: 100%; }
+p.s-source { font-size: 0.8em; color: #666; }
+"#;
+
+/// a chapter of the book, one per top-level section
+struct Chapter {
+    title: String,
+    /// anchor of the section, also the name of its file
+    id: String,
+    body: String,
+    /// `###` headings of the chapter, for the nav document
+    headings: Vec<(String, String)>,
+}
+
+/// an image referenced by the docs which is copied into the book
+struct Image {
+    /// path inside OEBPS/
+    href: String,
+    media_type: &'static str,
+    content: Vec<u8>,
+}
+
+/// writes an EPUB 3 book with one chapter per section
+pub(crate) fn write_epub(
+    sych_cfg: &SychConfig,
+    root: &Path,
+    docs_index: &IndexMap<String, Section>,
+    out: impl Write + Seek,
+) -> Result<()> {
+    let mut renderer = Renderer {
+        root,
+        anchors: Anchors::build(docs_index),
+        syntaxes: SyntaxSet::load_defaults_newlines(),
+        images: vec![],
+        image_paths: HashMap::new(),
+    };
+
+    let mut chapters = vec![];
+    for (title, section) in docs_index {
+        chapters.push(renderer.chapter(title, section));
+    }
+    let themes = ThemeSet::load_defaults();
+    let code_css = css_for_theme_with_class_style(&themes.themes[CODE_THEME], ClassStyle::Spaced)
+        .map_err(Error::render)?;
+
+    write_book(out, sych_cfg, &chapters, &renderer.images, &code_css).map_err(Error::render)
+}
+
+/// the zip archive of the book, its entries in the order EPUB wants
+fn write_book(
+    out: impl Write + Seek,
+    sych_cfg: &SychConfig,
+    chapters: &[Chapter],
+    images: &[Image],
+    code_css: &str,
+) -> ZipResult<()> {
+    let mut zip = ZipWriter::new(out);
+    // the mimetype has to be the first entry and must not be compressed
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER_XML.as_bytes())?;
+
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(package_document(sych_cfg, chapters, images).as_bytes())?;
+
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(nav_document(sych_cfg, chapters).as_bytes())?;
+
+    zip.start_file("OEBPS/title.xhtml", deflated)?;
+    zip.write_all(title_page(sych_cfg).as_bytes())?;
+
+    zip.start_file("OEBPS/style.css", deflated)?;
+    zip.write_all(EPUB_CSS.as_bytes())?;
+    zip.write_all(code_css.as_bytes())?;
+
+    for chapter in chapters {
+        zip.start_file(format!("OEBPS/{}.xhtml", chapter.id), deflated)?;
+        zip.write_all(xhtml_page(&chapter.title, &chapter.body).as_bytes())?;
+    }
+    for image in images {
+        zip.start_file(format!("OEBPS/{}", image.href), deflated)?;
+        zip.write_all(&image.content)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn package_document(sych_cfg: &SychConfig, chapters: &[Chapter], images: &[Image]) -> String {
+    let meta = &sych_cfg.meta;
+    let mut opf = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="uid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+"#,
+    );
+    opf.push_str(&format!(
+        "    <dc:identifier id=\"uid\">urn:sych:{}:{}</dc:identifier>\n",
+        escape(&crate::anchor::slugify(&meta.title)),
+        escape(&meta.version)
+    ));
+    opf.push_str(&format!(
+        "    <dc:title>{}</dc:title>\n",
+        escape(&meta.title)
+    ));
+    opf.push_str("    <dc:language>en</dc:language>\n");
+    for author in &meta.authors {
+        opf.push_str(&format!(
+            "    <dc:creator>{}</dc:creator>\n",
+            escape(author)
+        ));
+    }
+    opf.push_str(&format!(
+        "    <dc:description>{}</dc:description>\n",
+        escape(&meta.description)
+    ));
+    opf.push_str(&format!(
+        "    <meta property=\"dcterms:modified\">{}</meta>\n",
+        utc_timestamp()
+    ));
+    opf.push_str(&format!(
+        "    <meta name=\"version\" content=\"{}\"/>\n",
+        escape(&meta.version)
+    ));
+    opf.push_str("  </metadata>\n  <manifest>\n");
+    opf.push_str(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    opf.push_str(
+        "    <item id=\"title\" href=\"title.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+    );
+    opf.push_str("    <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        opf.push_str(&format!(
+            "    <item id=\"chapter-{i}\" href=\"{}.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+            chapter.id
+        ));
+    }
+    for (i, image) in images.iter().enumerate() {
+        opf.push_str(&format!(
+            "    <item id=\"image-{i}\" href=\"{}\" media-type=\"{}\"/>\n",
+            image.href, image.media_type
+        ));
+    }
+    opf.push_str("  </manifest>\n  <spine>\n    <itemref idref=\"title\"/>\n");
+    for i in 0..chapters.len() {
+        opf.push_str(&format!("    <itemref idref=\"chapter-{i}\"/>\n"));
+    }
+    opf.push_str("  </spine>\n</package>\n");
+    opf
+}
+
+fn nav_document(sych_cfg: &SychConfig, chapters: &[Chapter]) -> String {
+    let mut nav = String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+    nav.push_str(&format!(
+        "<li><a href=\"title.xhtml\">{}</a></li>\n",
+        escape(&sych_cfg.meta.title)
+    ));
+    for chapter in chapters {
+        nav.push_str(&format!(
+            "<li><a href=\"{}.xhtml\">{}</a>",
+            chapter.id,
+            escape(&chapter.title)
+        ));
+        if !chapter.headings.is_empty() {
+            nav.push_str("\n<ol>\n");
+            for (id, text) in &chapter.headings {
+                nav.push_str(&format!(
+                    "<li><a href=\"{}.xhtml#{}\">{}</a></li>\n",
+                    chapter.id,
+                    xml_id(id),
+                    escape(text)
+                ));
+            }
+            nav.push_str("</ol>\n");
+        }
+        nav.push_str("</li>\n");
+    }
+    nav.push_str("</ol>\n</nav>");
+    xhtml_page("Contents", &nav)
+}
+
+fn title_page(sych_cfg: &SychConfig) -> String {
+    let meta = &sych_cfg.meta;
+    let mut body = format!("<h1>{}</h1>\n", escape(&meta.title));
+    body.push_str(&format!("<p>{}</p>\n", escape(&meta.description)));
+    body.push_str(&format!("<p>Version {}</p>\n", escape(&meta.version)));
+    if !meta.authors.is_empty() {
+        body.push_str(&format!("<p>{}</p>\n", escape(&meta.authors.join(", "))));
+    }
+    xhtml_page(&meta.title, &body)
+}
+
+fn xhtml_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="en" lang="en">
+<head>
+<meta charset="UTF-8"/>
+<title>{}</title>
+<link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+{}
+</body>
+</html>
+"#,
+        escape(title),
+        body
+    )
+}
+
+struct Renderer<'a> {
+    root: &'a Path,
+    anchors: Anchors,
+    syntaxes: SyntaxSet,
+    images: Vec<Image>,
+    /// image path relative to the docs root -> href inside the book
+    image_paths: HashMap<String, String>,
+}
+
+/// state of the chapter being rendered
+struct ChapterState {
+    id: String,
+    source: String,
+    slugs: Slugger,
+    headings: Vec<(String, String)>,
+}
+
+impl Renderer<'_> {
+    fn chapter(&mut self, title: &str, section: &Section) -> Chapter {
+        let id = self.anchors.sections[title].clone();
+        let mut state = ChapterState {
+            id: id.clone(),
+            source: String::new(),
+            slugs: Slugger::default(),
+            headings: vec![],
+        };
+
+        let mut body = format!("<h1 id=\"{}\">{}</h1>\n", xml_id(&id), escape(title));
+        for part in &section.parts {
+            state.source = part.source.clone();
+            // heading ids have to be handed out in the same order as the
+            // html output, otherwise links between files would not match
+            if section.is_merged() {
+                let heading = part.origin_heading();
+                if let Block::Header(spans, _) = &heading {
+                    state.slugs.slug(&spans_to_text(spans));
+                }
+                body.push_str(&format!(
+                    "<p class=\"s-source\">{}</p>\n",
+                    escape(&part.source)
+                ));
+            }
+            self.blocks(&mut body, &part.blocks, &mut state);
+        }
+
+        Chapter {
+            title: title.to_owned(),
+            id,
+            body,
+            headings: state.headings,
+        }
+    }
+
+    fn blocks(&mut self, out: &mut String, blocks: &[Block], state: &mut ChapterState) {
+        for block in blocks {
+            match block {
+                Block::Header(spans, level) => {
+                    let text = spans_to_text(spans);
+                    let id = format!("{}/{}", state.id, state.slugs.slug(&text));
+                    // the chapter title takes h1
+                    let level = level.clamp(&3, &6) - 1;
+                    if level == 2 {
+                        state.headings.push((id.clone(), text));
+                    }
+                    out.push_str(&format!(
+                        "<h{level} id=\"{}\">{}</h{level}>\n",
+                        xml_id(&id),
+                        self.spans(spans, state)
+                    ));
+                }
+                Block::Paragraph(spans) => {
+                    out.push_str(&format!("<p>{}</p>\n", self.spans(spans, state)));
+                }
+                Block::Blockquote(blocks) => {
+                    out.push_str("<blockquote>\n");
+                    self.blocks(out, blocks, state);
+                    out.push_str("</blockquote>\n");
+                }
+                Block::CodeBlock(meta, code) => {
+                    let lang = meta
+                        .as_deref()
+                        .and_then(|m| m.split_whitespace().next())
+                        .unwrap_or_default();
+                    out.push_str(&self.code(lang, code));
+                }
+                Block::OrderedList(items, _) => {
+                    out.push_str("<ol>\n");
+                    self.items(out, items, state);
+                    out.push_str("</ol>\n");
+                }
+                Block::UnorderedList(items) => {
+                    out.push_str("<ul>\n");
+                    self.items(out, items, state);
+                    out.push_str("</ul>\n");
+                }
+                Block::Raw(text) => out.push_str(&format!("<p>{}</p>\n", escape(text))),
+                Block::Hr => out.push_str("<hr/>\n"),
+            }
+        }
+    }
+
+    fn items(&mut self, out: &mut String, items: &[ListItem], state: &mut ChapterState) {
+        for item in items {
+            out.push_str("<li>");
+            match item {
+                ListItem::Simple(spans) => out.push_str(&self.spans(spans, state)),
+                ListItem::Paragraph(blocks) => self.blocks(out, blocks, state),
+            }
+            out.push_str("</li>\n");
+        }
+    }
+
+    fn spans(&mut self, spans: &[Span], state: &ChapterState) -> String {
+        let mut out = String::new();
+        for span in spans {
+            match span {
+                Span::Break => out.push_str("<br/>"),
+                Span::Text(t) => {
+                    if !t.starts_with("<!--") {
+                        out.push_str(&escape(t));
+                    }
+                }
+                Span::Code(c) => out.push_str(&format!("<code>{}</code>", escape(c))),
+                Span::Link(text, href, _) => {
+                    let text = escape(text);
+                    match self.anchors.resolve(&state.source, href) {
+                        Link::Internal(anchor) => {
+                            let section = anchor.split('/').next().unwrap_or_default();
+                            out.push_str(&format!(
+                                "<a href=\"{section}.xhtml#{}\">{text}</a>",
+                                xml_id(&anchor)
+                            ));
+                        }
+                        Link::External => {
+                            out.push_str(&format!("<a href=\"{}\">{text}</a>", escape(href)));
+                        }
+                        // there is nothing inside the book to point to
+                        Link::Broken | Link::MissingAnchor | Link::Asset => out.push_str(&text),
+                    }
+                }
+                Span::Image(alt, src, _) => match self.image(&state.source, src) {
+                    Some(href) => out.push_str(&format!(
+                        "<img src=\"{}\" alt=\"{}\"/>",
+                        escape(&href),
+                        escape(alt)
+                    )),
+                    // remote images are not allowed inside the book
+                    None => out.push_str(&escape(alt)),
+                },
+                Span::Emphasis(inner) => {
+                    out.push_str(&format!("<em>{}</em>", self.spans(inner, state)));
+                }
+                Span::Strong(inner) => {
+                    out.push_str(&format!("<strong>{}</strong>", self.spans(inner, state)));
+                }
+            }
+        }
+        out
+    }
+
+    /// code is highlighted up front as e-readers do not run scripts
+    fn code(&self, lang: &str, code: &str) -> String {
+        let syntax = self
+            .syntaxes
+            .find_syntax_by_token(lang)
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
+        let mut generator =
+            ClassedHTMLGenerator::new_with_class_style(syntax, &self.syntaxes, ClassStyle::Spaced);
+        for line in LinesWithEndings::from(code) {
+            if generator
+                .parse_html_for_line_which_includes_newline(line)
+                .is_err()
+            {
+                return format!("<pre><code>{}</code></pre>\n", escape(code));
+            }
+        }
+        format!(
+            "<pre class=\"code\"><code>{}</code></pre>\n",
+            generator.finalize()
+        )
+    }
+
+    /// copies a local image into the book and returns its href, every
+    /// image is added once no matter how often it is referenced
+    fn image(&mut self, source: &str, src: &str) -> Option<String> {
+        let path = src.split(['#', '?']).next().unwrap_or_default();
+        let target = resolve_path(source, path)?;
+        if let Some(href) = self.image_paths.get(&target) {
+            return Some(href.clone());
+        }
+
+        let extension = Path::new(&target)
+            .extension()?
+            .to_string_lossy()
+            .to_lowercase();
+        let media_type = match extension.as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "svg" => "image/svg+xml",
+            "webp" => "image/webp",
+            _ => return None,
+        };
+        let content = std::fs::read(self.root.join(&target)).ok()?;
+        let href = format!("images/{}.{extension}", self.images.len());
+        self.images.push(Image {
+            href: href.clone(),
+            media_type,
+            content,
+        });
+        self.image_paths.insert(target, href.clone());
+        Some(href)
+    }
+}
+
+/// anchors contain "/" which is not allowed in xml ids
+fn xml_id(anchor: &str) -> String {
+    format!("s-{}", anchor.replace('/', "--"))
+}
+
+/// escapes text for xhtml, entities which xml knows about are kept as
+/// the markdown parser leaves them in the text as written
+pub(crate) fn escape(text: &str) -> String {
+    const ENTITIES: &[&str] = &["&amp;", "&lt;", "&gt;", "&quot;", "&apos;", "&#"];
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        match c {
+            '&' if ENTITIES.iter().any(|e| text[i..].starts_with(e)) => out.push('&'),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// current time as required by dcterms:modified, e.g. 2024-05-01T12:00:00Z
+fn utc_timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    timestamp(secs as i64)
+}
+
+/// a time in seconds since the epoch as e.g. 2024-05-01T12:00:00Z
+pub(crate) fn timestamp(secs: i64) -> String {
+    let (days, rest) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    let (year, month, day) = civil_date(days);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+/// year, month and day of a number of days since the epoch, by Howard
+/// Hinnant
+pub(crate) fn civil_date(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,288 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+
+const RED: &str = "1;31";
+const YELLOW: &str = "1;33";
+const BLUE: &str = "1;34";
+const BOLD: &str = "1";
+
+/// everything the public api of sych can fail with
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// sych.toml, or one of the layers on top of it, is missing or invalid
+    Config {
+        message: String,
+        span: Option<SourceSpan>,
+    },
+    /// the markdown files making up the docs, or a snippet included by
+    /// one of them, could not be found
+    Discovery {
+        message: String,
+        span: Option<SourceSpan>,
+    },
+    /// a markdown file could not be parsed, e.g. its front matter
+    Parse {
+        file: String,
+        message: String,
+        span: Option<SourceSpan>,
+    },
+    /// titles declared by more than one file with the `error` strategy
+    DuplicateSections(Vec<String>),
+    /// an output could not be rendered
+    Render(String),
+    /// a code fence cannot be handed to its extension
+    Extension {
+        name: String,
+        message: String,
+        span: Option<SourceSpan>,
+    },
+    /// no section matches the title or anchor which was asked for
+    UnknownSection {
+        query: String,
+        sections: Vec<String>,
+    },
+    /// a pass of the transform pipeline failed
+    Transform { name: String, message: String },
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// every error found by a step which keeps going after the first one
+    Multiple(Vec<Error>),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// a place in a markdown or toml file which a diagnostic points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpan {
+    pub file: String,
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    /// number of characters to underline
+    pub width: usize,
+    /// the line the span is on, as written in the file
+    pub source_line: String,
+}
+
+impl SourceSpan {
+    /// the span of a byte range of `content`, cut at the end of its line
+    pub(crate) fn new(file: &str, content: &str, range: Range<usize>) -> Self {
+        let start = range.start.min(content.len());
+        let line_start = content[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = content[start..]
+            .find('\n')
+            .map(|i| start + i)
+            .unwrap_or(content.len());
+        let end = range.end.clamp(start, line_end);
+        SourceSpan {
+            file: file.to_owned(),
+            line: content[..start].matches('\n').count() + 1,
+            column: content[line_start..start].chars().count() + 1,
+            width: content[start..end].chars().count().max(1),
+            source_line: content[line_start..line_end].trim_end().to_owned(),
+        }
+    }
+
+    /// the first occurrence of `needle`, markdown blocks do not keep
+    /// their positions so this is how they are found again
+    pub(crate) fn find(file: &str, content: &str, needle: &str) -> Option<Self> {
+        let start = content.find(needle)?;
+        Some(SourceSpan::new(file, content, start..start + needle.len()))
+    }
+}
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.into();
+        move |source| Error::Io { path, source }
+    }
+
+    pub(crate) fn render(e: impl fmt::Display) -> Self {
+        Error::Render(e.to_string())
+    }
+
+    pub(crate) fn config(message: impl Into<String>) -> Self {
+        Error::Config {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// a single error stays as it is, several become `Multiple`
+    pub(crate) fn collect(mut errors: Vec<Error>) -> Result<()> {
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(Error::Multiple(errors)),
+        }
+    }
+
+    /// exit code of the `sych` binary for this error, `Multiple` takes
+    /// the code of its first error
+    ///
+    /// | code | error |
+    /// |------|-------|
+    /// | 3 | configuration |
+    /// | 4 | discovery, unknown or duplicate sections |
+    /// | 5 | markdown parsing |
+    /// | 6 | rendering |
+    /// | 7 | extensions |
+    /// | 8 | reading or writing files |
+    /// | 9 | transforms |
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Config { .. } => 3,
+            Error::Discovery { .. }
+            | Error::UnknownSection { .. }
+            | Error::DuplicateSections(_) => 4,
+            Error::Parse { .. } => 5,
+            Error::Render(_) => 6,
+            Error::Extension { .. } => 7,
+            Error::Io { .. } => 8,
+            Error::Transform { .. } => 9,
+            Error::Multiple(errors) => errors.first().map(Error::exit_code).unwrap_or(1),
+        }
+    }
+
+    /// every error along with the line it points at, the way rustc
+    /// prints them, colored with ANSI escapes when `color` is set
+    pub fn report(&self, color: bool) -> String {
+        match self {
+            Error::Multiple(errors) => errors.iter().map(|e| e.report(color)).collect(),
+            _ => report(Level::Error, &self.message(), self.span(), color),
+        }
+    }
+
+    pub fn span(&self) -> Option<&SourceSpan> {
+        match self {
+            Error::Config { span, .. }
+            | Error::Discovery { span, .. }
+            | Error::Parse { span, .. }
+            | Error::Extension { span, .. } => span.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// what went wrong, without where
+    fn message(&self) -> String {
+        match self {
+            Error::Config { message, .. } | Error::Discovery { message, .. } => message.clone(),
+            Error::Parse {
+                file,
+                message,
+                span,
+            } => match span {
+                Some(_) => message.clone(),
+                None => format!("{file}: {message}"),
+            },
+            Error::DuplicateSections(titles) => format!(
+                "duplicate sections found ({}), set `merge` in [doc] or [doc.sections] to resolve them",
+                titles.join(", ")
+            ),
+            Error::Render(message) => format!("rendering failed, {message}"),
+            Error::Extension { name, message, .. } => {
+                format!("payload of extension \"{name}\" cannot be parsed, {message}")
+            }
+            Error::UnknownSection { query, sections } => format!(
+                "no section named \"{query}\", the sections are: {}",
+                sections.join(", ")
+            ),
+            Error::Transform { name, message } => {
+                format!("transform \"{name}\" failed, {message}")
+            }
+            Error::Io { path, source } => format!("{}: {source}", path.display()),
+            Error::Multiple(errors) => {
+                let first = errors.first().map(Error::to_string).unwrap_or_default();
+                format!("{first} (and {} more errors)", errors.len().saturating_sub(1))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = self.span() {
+            write!(f, "{}:{}:{}: ", span.file, span.line, span.column)?;
+        }
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Level {
+    Error,
+    Warning,
+}
+
+/// a diagnostic with a snippet of the line it points at:
+///
+/// ```text
+/// error: invalid type: string "a", expected usize
+///  --> sych.toml:2:13
+///   |
+/// 2 | toc_depth = "a"
+///   |             ^^^
+/// ```
+pub(crate) fn report(
+    level: Level,
+    message: &str,
+    span: Option<&SourceSpan>,
+    color: bool,
+) -> String {
+    let paint = |codes: &str, text: &str| {
+        if color {
+            format!("\x1b[{codes}m{text}\x1b[0m")
+        } else {
+            text.to_owned()
+        }
+    };
+    let (label, level_color) = match level {
+        Level::Error => ("error", RED),
+        Level::Warning => ("warning", YELLOW),
+    };
+    let mut out = format!(
+        "{}{}\n",
+        paint(level_color, label),
+        paint(BOLD, &format!(": {message}"))
+    );
+    let Some(span) = span else {
+        return out;
+    };
+
+    let line_number = span.line.to_string();
+    let gutter = " ".repeat(line_number.len());
+    let bar = paint(BLUE, "|");
+    out.push_str(&format!(
+        "{gutter}{} {}:{}:{}\n",
+        paint(BLUE, "-->"),
+        span.file,
+        span.line,
+        span.column
+    ));
+    out.push_str(&format!("{gutter} {bar}\n"));
+    out.push_str(&format!(
+        "{} {bar} {}\n",
+        paint(BLUE, &line_number),
+        span.source_line
+    ));
+    out.push_str(&format!(
+        "{gutter} {bar} {}{}\n",
+        " ".repeat(span.column - 1),
+        paint(level_color, &"^".repeat(span.width))
+    ));
+    out
+}
@@ -0,0 +1,487 @@
+use indexmap::IndexMap;
+use markdown::{Block, ListItem, Span};
+use serde::Serialize;
+
+use crate::frontmatter::FrontMatter;
+use crate::git::GitInfo;
+use crate::indexer::Section;
+use crate::transpiler::{Doc, TocEntry};
+use crate::{Meta, SychConfig};
+
+/// bumped whenever a field is removed or changes its meaning,
+/// see schemas/sych-index.schema.json
+const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// the whole index as it is written by `sych build --format json`
+#[derive(Serialize, Debug)]
+pub(crate) struct JsonIndex<'a> {
+    schema_version: u32,
+    meta: &'a Meta,
+    sections: Vec<JsonSection<'a>>,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonSection<'a> {
+    /// anchor of the section, the same one used by the html output
+    id: &'a str,
+    title: &'a str,
+    /// rendered html of the whole section
+    html: &'a str,
+    /// headings of the section as a tree
+    headings: Vec<JsonHeading>,
+    /// every markdown file which contributed to the section
+    sources: Vec<JsonSource<'a>>,
+    /// newest commit and contributors of all the sources
+    git: Option<GitInfo>,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonHeading {
+    level: usize,
+    id: String,
+    text: String,
+    children: Vec<JsonHeading>,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonSource<'a> {
+    /// path of the markdown file relative to the docs root
+    path: &'a str,
+    /// the `##` title as written in the file
+    title: &'a str,
+    front_matter: &'a FrontMatter,
+    blocks: Vec<JsonBlock>,
+    git: Option<&'a GitInfo>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonBlock {
+    Heading {
+        level: usize,
+        spans: Vec<JsonSpan>,
+    },
+    Paragraph {
+        spans: Vec<JsonSpan>,
+    },
+    Blockquote {
+        blocks: Vec<JsonBlock>,
+    },
+    Code {
+        lang: Option<String>,
+        code: String,
+    },
+    List {
+        ordered: bool,
+        items: Vec<Vec<JsonBlock>>,
+    },
+    Raw {
+        text: String,
+    },
+    Rule,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonSpan {
+    Break,
+    Text {
+        text: String,
+    },
+    Code {
+        code: String,
+    },
+    Link {
+        text: String,
+        href: String,
+        title: Option<String>,
+    },
+    Image {
+        alt: String,
+        src: String,
+        title: Option<String>,
+    },
+    Emphasis {
+        spans: Vec<JsonSpan>,
+    },
+    Strong {
+        spans: Vec<JsonSpan>,
+    },
+}
+
+impl<'a> JsonIndex<'a> {
+    pub fn new(
+        sych_cfg: &'a SychConfig,
+        docs_index: &'a IndexMap<String, Section>,
+        doc: &'a Doc,
+    ) -> Self {
+        let sections = docs_index
+            .iter()
+            .zip(doc.contents.iter())
+            .map(|((title, section), content)| JsonSection {
+                id: &content.id,
+                title,
+                html: &content.html,
+                headings: heading_tree(&content.headings),
+                sources: section
+                    .parts
+                    .iter()
+                    .map(|part| JsonSource {
+                        path: &part.source,
+                        title: &part.title,
+                        front_matter: &part.front_matter,
+                        blocks: part.blocks.iter().map(JsonBlock::from).collect(),
+                        git: part.git.as_ref(),
+                    })
+                    .collect(),
+                git: section.git(),
+            })
+            .collect();
+
+        JsonIndex {
+            schema_version: JSON_SCHEMA_VERSION,
+            meta: &sych_cfg.meta,
+            sections,
+        }
+    }
+}
+
+/// nests every heading under the closest preceding heading of a lower level
+fn heading_tree(headings: &[TocEntry]) -> Vec<JsonHeading> {
+    let mut roots: Vec<JsonHeading> = vec![];
+    for heading in headings {
+        let node = JsonHeading {
+            level: heading.level,
+            id: heading.id.clone(),
+            text: heading.text.clone(),
+            children: vec![],
+        };
+
+        let mut siblings = &mut roots;
+        while siblings
+            .last()
+            .is_some_and(|parent| parent.level < node.level)
+        {
+            siblings = &mut siblings.last_mut().unwrap().children;
+        }
+        siblings.push(node);
+    }
+    roots
+}
+
+impl From<&Block> for JsonBlock {
+    fn from(block: &Block) -> Self {
+        match block {
+            Block::Header(spans, level) => JsonBlock::Heading {
+                level: *level,
+                spans: json_spans(spans),
+            },
+            Block::Paragraph(spans) => JsonBlock::Paragraph {
+                spans: json_spans(spans),
+            },
+            Block::Blockquote(blocks) => JsonBlock::Blockquote {
+                blocks: blocks.iter().map(JsonBlock::from).collect(),
+            },
+            Block::CodeBlock(lang, code) => JsonBlock::Code {
+                lang: lang.clone(),
+                code: code.clone(),
+            },
+            Block::OrderedList(items, _) => JsonBlock::List {
+                ordered: true,
+                items: json_items(items),
+            },
+            Block::UnorderedList(items) => JsonBlock::List {
+                ordered: false,
+                items: json_items(items),
+            },
+            Block::Raw(text) => JsonBlock::Raw { text: text.clone() },
+            Block::Hr => JsonBlock::Rule,
+        }
+    }
+}
+
+/// every list item is a list of blocks, simple items become a paragraph
+fn json_items(items: &[ListItem]) -> Vec<Vec<JsonBlock>> {
+    items
+        .iter()
+        .map(|item| match item {
+            ListItem::Simple(spans) => vec![JsonBlock::Paragraph {
+                spans: json_spans(spans),
+            }],
+            ListItem::Paragraph(blocks) => blocks.iter().map(JsonBlock::from).collect(),
+        })
+        .collect()
+}
+
+fn json_spans(spans: &[Span]) -> Vec<JsonSpan> {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Break => JsonSpan::Break,
+            Span::Text(text) => JsonSpan::Text { text: text.clone() },
+            Span::Code(code) => JsonSpan::Code { code: code.clone() },
+            Span::Link(text, href, title) => JsonSpan::Link {
+                text: text.clone(),
+                href: href.clone(),
+                title: title.clone(),
+            },
+            Span::Image(alt, src, title) => JsonSpan::Image {
+                alt: alt.clone(),
+                src: src.clone(),
+                title: title.clone(),
+            },
+            Span::Emphasis(spans) => JsonSpan::Emphasis {
+                spans: json_spans(spans),
+            },
+            Span::Strong(spans) => JsonSpan::Strong {
+                spans: json_spans(spans),
+            },
+        })
+        .collect()
+}
+
+/// flavour of the combined single-file export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TextFlavor {
+    Markdown,
+    Text,
+}
+
+/// size of the combined export, tokens are estimated with the usual
+/// rule of thumb of ~4 characters per token
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct TextStats {
+    pub sections: Vec<(String, usize)>,
+    pub words: usize,
+    pub characters: usize,
+}
+
+impl TextStats {
+    pub fn estimated_tokens(characters: usize) -> usize {
+        characters.div_ceil(4)
+    }
+}
+
+/// concatenates every section, in navigation order, into a single
+/// normalized markdown or plain text document
+pub(crate) fn combined_text(
+    sych_cfg: &SychConfig,
+    docs_index: &IndexMap<String, Section>,
+    flavor: TextFlavor,
+) -> (String, TextStats) {
+    let meta = &sych_cfg.meta;
+    let mut out = String::new();
+    match flavor {
+        TextFlavor::Markdown => {
+            out.push_str(&format!("# {}\n\n> {}\n\n", meta.title, meta.description))
+        }
+        TextFlavor::Text => out.push_str(&format!(
+            "{}\n{}\n\n{}\n\n",
+            meta.title,
+            "=".repeat(meta.title.chars().count()),
+            meta.description
+        )),
+    }
+    out.push_str(&format!("version: {}\n", meta.version));
+    if !meta.authors.is_empty() {
+        out.push_str(&format!("authors: {}\n", meta.authors.join(", ")));
+    }
+
+    let mut stats = TextStats::default();
+    for (title, section) in docs_index {
+        let mut chunk = String::new();
+        match flavor {
+            TextFlavor::Markdown => chunk.push_str(&format!("\n## {title}\n")),
+            TextFlavor::Text => chunk.push_str(&format!(
+                "\n\n{}\n{}\n",
+                title.to_uppercase(),
+                "-".repeat(title.chars().count())
+            )),
+        }
+
+        for part in &section.parts {
+            chunk.push_str(&format!("\nsource: {}\n", part.source));
+            for (key, value) in front_matter_pairs(&part.front_matter) {
+                chunk.push_str(&format!("{key}: {value}\n"));
+            }
+            for block in &part.blocks {
+                chunk.push('\n');
+                write_block(&mut chunk, block, flavor, "");
+            }
+        }
+
+        stats.sections.push((title.clone(), chunk.chars().count()));
+        out.push_str(&chunk);
+    }
+
+    stats.words = out.split_whitespace().count();
+    stats.characters = out.chars().count();
+    (out, stats)
+}
+
+/// plain text of blocks, the way the text export writes them
+pub(crate) fn blocks_text(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        out.push('\n');
+        write_block(&mut out, block, TextFlavor::Text, "");
+    }
+    out
+}
+
+/// front matter as `key: value` pairs, keys without a value are left out
+fn front_matter_pairs(front_matter: &FrontMatter) -> Vec<(String, String)> {
+    let Ok(serde_yaml::Value::Mapping(mapping)) = serde_yaml::to_value(front_matter) else {
+        return vec![];
+    };
+    mapping
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .filter_map(|(key, value)| {
+            let key = key.as_str()?.to_owned();
+            let value = match value {
+                serde_yaml::Value::String(s) => s,
+                other => serde_yaml::to_string(&other)
+                    .ok()?
+                    .trim()
+                    .replace('\n', " "),
+            };
+            Some((key, value))
+        })
+        .collect()
+}
+
+fn write_block(out: &mut String, block: &Block, flavor: TextFlavor, indent: &str) {
+    match block {
+        Block::Header(spans, level) => {
+            let text = spans_to_markup(spans, flavor);
+            match flavor {
+                TextFlavor::Markdown => {
+                    out.push_str(&format!("{indent}{} {text}\n", "#".repeat(*level)));
+                }
+                TextFlavor::Text => out.push_str(&format!("{indent}{text}\n")),
+            }
+        }
+        Block::Paragraph(spans) => {
+            let text = spans_to_markup(spans, flavor);
+            for line in text.lines() {
+                out.push_str(&format!("{indent}{line}\n"));
+            }
+        }
+        Block::Blockquote(blocks) => {
+            let quote = match flavor {
+                TextFlavor::Markdown => format!("{indent}> "),
+                TextFlavor::Text => format!("{indent}    "),
+            };
+            for (i, block) in blocks.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(quote.trim_end());
+                    out.push('\n');
+                }
+                write_block(out, block, flavor, &quote);
+            }
+        }
+        Block::CodeBlock(lang, code) => match flavor {
+            TextFlavor::Markdown => {
+                out.push_str(&format!(
+                    "{indent}```{}\n",
+                    lang.as_deref().unwrap_or_default()
+                ));
+                for line in code.lines() {
+                    out.push_str(&format!("{indent}{line}\n"));
+                }
+                out.push_str(&format!("{indent}```\n"));
+            }
+            TextFlavor::Text => {
+                for line in code.lines() {
+                    out.push_str(&format!("{indent}    {line}\n"));
+                }
+            }
+        },
+        Block::OrderedList(items, _) => write_items(out, items, flavor, indent, true),
+        Block::UnorderedList(items) => write_items(out, items, flavor, indent, false),
+        Block::Raw(text) => out.push_str(&format!("{indent}{text}\n")),
+        Block::Hr => match flavor {
+            TextFlavor::Markdown => out.push_str(&format!("{indent}---\n")),
+            TextFlavor::Text => out.push('\n'),
+        },
+    }
+}
+
+fn write_items(
+    out: &mut String,
+    items: &[ListItem],
+    flavor: TextFlavor,
+    indent: &str,
+    ordered: bool,
+) {
+    for (i, item) in items.iter().enumerate() {
+        let marker = if ordered {
+            format!("{}. ", i + 1)
+        } else {
+            "- ".to_owned()
+        };
+        match item {
+            ListItem::Simple(spans) => {
+                out.push_str(&format!(
+                    "{indent}{marker}{}\n",
+                    spans_to_markup(spans, flavor)
+                ));
+            }
+            ListItem::Paragraph(blocks) => {
+                // the first block goes next to the marker, the rest is
+                // indented below it
+                let nested = format!("{indent}{}", " ".repeat(marker.len()));
+                let mut item = String::new();
+                for block in blocks {
+                    write_block(&mut item, block, flavor, &nested);
+                }
+                // blocks end their own line, unless none of them
+                // rendered anything
+                let item = item.trim_start();
+                out.push_str(&format!("{indent}{marker}{item}"));
+                if !item.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+}
+
+fn spans_to_markup(spans: &[Span], flavor: TextFlavor) -> String {
+    let mut text = String::new();
+    for span in spans {
+        match (span, flavor) {
+            (Span::Break, _) => text.push('\n'),
+            (Span::Text(t), _) => {
+                if !t.starts_with("<!--") {
+                    text.push_str(t);
+                }
+            }
+            (Span::Code(c), TextFlavor::Markdown) => text.push_str(&format!("`{c}`")),
+            (Span::Code(c), TextFlavor::Text) => text.push_str(c),
+            (Span::Link(t, href, _), TextFlavor::Markdown) => {
+                text.push_str(&format!("[{t}]({href})"));
+            }
+            (Span::Link(t, href, _), TextFlavor::Text) => text.push_str(&format!("{t} ({href})")),
+            (Span::Image(alt, src, _), TextFlavor::Markdown) => {
+                text.push_str(&format!("![{alt}]({src})"));
+            }
+            (Span::Image(alt, src, _), TextFlavor::Text) => {
+                text.push_str(&format!("[image: {alt}] ({src})"));
+            }
+            (Span::Emphasis(inner), TextFlavor::Markdown) => {
+                text.push_str(&format!("*{}*", spans_to_markup(inner, flavor)));
+            }
+            (Span::Strong(inner), TextFlavor::Markdown) => {
+                text.push_str(&format!("**{}**", spans_to_markup(inner, flavor)));
+            }
+            (Span::Emphasis(inner) | Span::Strong(inner), TextFlavor::Text) => {
+                text.push_str(&spans_to_markup(inner, flavor));
+            }
+        }
+    }
+    text
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ExternalCheckConfig;
+
+const CACHE_FILE: &str = "external-links.json";
+
+pub(crate) const DEFAULT_CONCURRENCY: usize = 8;
+pub(crate) const DEFAULT_TIMEOUT_SECS: u64 = 10;
+pub(crate) const DEFAULT_RATE_LIMIT: f64 = 4.0;
+pub(crate) const DEFAULT_CACHE_TTL_HOURS: u64 = 24;
+const MAX_REDIRECTS: u32 = 5;
+
+/// an http(s) link found in the docs
+#[derive(Debug, Clone)]
+pub(crate) struct ExternalLink {
+    pub url: String,
+    pub source: String,
+    pub line: Option<usize>,
+}
+
+/// result of requesting a single url
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct LinkStatus {
+    /// http status of the last response, none when the request failed
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    /// unix timestamp (seconds) of when the url was checked
+    pub checked_at: u64,
+}
+
+impl LinkStatus {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.status, Some(status) if status < 400)
+    }
+}
+
+/// successful results of previous runs, so that unchanged links are not
+/// requested on every check
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct LinkCache {
+    links: HashMap<String, LinkStatus>,
+}
+
+/// requests every unique url of the given links and returns the status
+/// of each url which was checked, urls filtered out by `allow` and
+/// `ignore` are left out
+pub(crate) fn check_links(
+    cfg: &ExternalCheckConfig,
+    cache_dir: &Path,
+    links: &[ExternalLink],
+) -> HashMap<String, LinkStatus> {
+    let cache_path = cache_dir.join(CACHE_FILE);
+    let mut cache: LinkCache = std::fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+
+    // a huge ttl in the user config means the cache never expires
+    let ttl = cfg
+        .cache_ttl
+        .unwrap_or(DEFAULT_CACHE_TTL_HOURS)
+        .saturating_mul(60 * 60);
+    let now = unix_now();
+    let mut results = HashMap::new();
+    let mut pending = vec![];
+    for link in links {
+        if results.contains_key(&link.url) || pending.contains(&link.url) {
+            continue;
+        }
+        if !should_check(cfg, &link.url) {
+            continue;
+        }
+        match cache.links.get(&link.url) {
+            Some(cached) if cached.is_ok() && now.saturating_sub(cached.checked_at) < ttl => {
+                results.insert(link.url.clone(), cached.clone());
+            }
+            _ => pending.push(link.url.clone()),
+        }
+    }
+
+    let checked = request_all(cfg, pending);
+    for (url, status) in checked {
+        if status.is_ok() {
+            cache.links.insert(url.clone(), status.clone());
+        } else {
+            cache.links.remove(&url);
+        }
+        results.insert(url, status);
+    }
+
+    // failing to write the cache only makes the next run slower
+    if std::fs::create_dir_all(cache_dir).is_ok() {
+        if let Ok(content) = serde_json::to_string_pretty(&cache) {
+            let _ = std::fs::write(&cache_path, content);
+        }
+    }
+
+    results
+}
+
+/// `allow` restricts the check to the matching urls, `ignore` skips
+/// the matching urls
+fn should_check(cfg: &ExternalCheckConfig, url: &str) -> bool {
+    if let Some(allow) = cfg.allow.as_ref() {
+        if !allow.iter().any(|pattern| matches_pattern(pattern, url)) {
+            return false;
+        }
+    }
+    if let Some(ignore) = cfg.ignore.as_ref() {
+        if ignore.iter().any(|pattern| matches_pattern(pattern, url)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// a pattern is matched against the whole url, `*` matches any run of
+/// characters e.g. "https://github.com/*/issues/*"
+fn matches_pattern(pattern: &str, url: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = url.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+/// checks the urls on a pool of worker threads, requests to the same
+/// host are spaced out according to the rate limit
+fn request_all(cfg: &ExternalCheckConfig, urls: Vec<String>) -> Vec<(String, LinkStatus)> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(
+            cfg.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS),
+        ))
+        .redirects(MAX_REDIRECTS)
+        .build();
+    let rate_limit = cfg.rate_limit.unwrap_or(DEFAULT_RATE_LIMIT);
+    let host_interval = if rate_limit > 0.0 {
+        // a tiny rate limit leaves more time between requests than fits
+        Duration::try_from_secs_f64(1.0 / rate_limit).unwrap_or(Duration::MAX)
+    } else {
+        Duration::ZERO
+    };
+    let concurrency = cfg.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1);
+
+    let queue = Mutex::new(urls);
+    // `None` once the rate limit leaves no time for another request
+    let next_request: Mutex<HashMap<String, Option<Instant>>> = Mutex::new(HashMap::new());
+    let results = Mutex::new(vec![]);
+    thread::scope(|scope| {
+        for _ in 0..concurrency {
+            scope.spawn(|| loop {
+                let Some(url) = queue.lock().unwrap().pop() else {
+                    break;
+                };
+
+                // reserve the next slot for this host before sleeping so
+                // that other workers queue up behind us
+                let wait = {
+                    let mut next_request = next_request.lock().unwrap();
+                    let now = Instant::now();
+                    let slot = match next_request.get(&host_of(&url)) {
+                        Some(None) => None,
+                        Some(Some(slot)) => Some((*slot).max(now)),
+                        None => Some(now),
+                    };
+                    if let Some(slot) = slot {
+                        next_request.insert(host_of(&url), slot.checked_add(host_interval));
+                    }
+                    slot.map(|slot| slot - now)
+                };
+
+                let status = match wait {
+                    Some(wait) => {
+                        thread::sleep(wait);
+                        request(&agent, &url)
+                    }
+                    None => LinkStatus {
+                        status: None,
+                        error: Some(
+                            "was not requested, the rate limit of its host allows no more requests"
+                                .to_owned(),
+                        ),
+                        checked_at: unix_now(),
+                    },
+                };
+                results.lock().unwrap().push((url, status));
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+/// HEAD is tried first as it is cheap, a lot of servers do not
+/// implement it properly so any failure is retried with GET
+fn request(agent: &ureq::Agent, url: &str) -> LinkStatus {
+    let mut result = agent.head(url).call();
+    if !matches!(result, Ok(ref response) if response.status() < 400) {
+        result = agent.get(url).call();
+    }
+
+    let (status, error) = match result {
+        Ok(response) => (Some(response.status()), None),
+        Err(ureq::Error::Status(status, _)) => (Some(status), None),
+        Err(ureq::Error::Transport(transport)) => (None, Some(transport.to_string())),
+    };
+    LinkStatus {
+        status,
+        error,
+        checked_at: unix_now(),
+    }
+}
+
+fn host_of(url: &str) -> String {
+    let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SourceSpan;
+use crate::{Error, Result};
+
+const FRONT_MATTER_FENCE: &str = "---";
+
+/// yaml block at the top of a markdown file, fenced by `---`
+///
+/// ```yaml
+/// ---
+/// toc: false
+/// ---
+/// ```
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct FrontMatter {
+    /// set to false to hide the headings of this file from the
+    /// "on this page" table of contents
+    pub toc: Option<bool>,
+    /// overrides `[doc] toc_depth` for the headings of this file
+    pub toc_depth: Option<usize>,
+    /// code the file describes, relative to sych.toml, the file is
+    /// flagged as possibly outdated once it changes too much
+    pub covers: Option<Vec<String>>,
+    /// people who wrote the notes of the file, listed instead of its
+    /// git contributors in the "who knows what" section
+    pub authors: Option<Vec<String>>,
+    /// adds a box above the content of the file which filters its lists
+    pub filter: Option<bool>,
+    /// keys which sych does not understand are kept as is
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// separates the front matter from the markdown content, files without
+/// front matter get the default one
+pub(crate) fn split_front_matter<'a>(
+    md_file_path: &str,
+    content: &'a str,
+) -> Result<(FrontMatter, &'a str)> {
+    let Some(rest) = content.strip_prefix(FRONT_MATTER_FENCE) else {
+        return Ok((FrontMatter::default(), content));
+    };
+    if !rest.starts_with('\n') && !rest.starts_with("\r\n") {
+        return Ok((FrontMatter::default(), content));
+    }
+
+    // the closing fence has to be on its own line
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if offset > 0 && line.trim_end() == FRONT_MATTER_FENCE {
+            let yaml = &rest[..offset];
+            let front_matter = if yaml.trim().is_empty() {
+                FrontMatter::default()
+            } else {
+                serde_yaml::from_str(yaml).map_err(|e| {
+                    // the location is part of the span already
+                    let message = e.to_string();
+                    let message = match message.rfind(" at line ") {
+                        Some(i) => &message[..i],
+                        None => &message,
+                    };
+                    // the yaml starts right after the opening fence
+                    let yaml_start = content.len() - rest.len();
+                    let span = e.location().map(|location| {
+                        let start = yaml_start + location.index();
+                        SourceSpan::new(md_file_path, content, start..start + 1)
+                    });
+                    Error::Parse {
+                        file: md_file_path.to_owned(),
+                        message: format!("invalid front matter, {message}"),
+                        span,
+                    }
+                })?
+            };
+            return Ok((front_matter, &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+
+    Ok((FrontMatter::default(), content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_files_without_front_matter_alone() {
+        for content in [
+            "## Setup\n",
+            "---title\n---\n## Setup\n",
+            "",
+            "\n---\ntoc: false\n---\n",
+        ] {
+            let (front_matter, rest) = split_front_matter("a.md", content).unwrap();
+            assert_eq!(front_matter.toc, None);
+            assert_eq!(rest, content);
+        }
+    }
+
+    #[test]
+    fn splits_the_front_matter_off() {
+        let content = "---\ntoc: false\ntoc_depth: 3\nowner: ana\n---\n## Setup\n";
+        let (front_matter, rest) = split_front_matter("a.md", content).unwrap();
+        assert_eq!(front_matter.toc, Some(false));
+        assert_eq!(front_matter.toc_depth, Some(3));
+        assert_eq!(front_matter.extra["owner"], "ana");
+        assert_eq!(rest, "## Setup\n");
+
+        let (front_matter, rest) = split_front_matter("a.md", "---\r\n---\r\n## A\n").unwrap();
+        assert_eq!(front_matter.toc, None);
+        assert_eq!(rest, "## A\n");
+    }
+
+    #[test]
+    fn keeps_an_unterminated_fence_as_content() {
+        let content = "---\ntoc: false\n## Setup\n";
+        let (front_matter, rest) = split_front_matter("a.md", content).unwrap();
+        assert_eq!(front_matter.toc, None);
+        assert_eq!(rest, content);
+    }
+
+    #[test]
+    fn reports_invalid_yaml_where_it_is() {
+        let content = "---\ntoc: false\ntoc_depth: deep\n---\n## Setup\n";
+        let Err(Error::Parse {
+            file,
+            message,
+            span,
+        }) = split_front_matter("a.md", content)
+        else {
+            panic!("the front matter is invalid");
+        };
+        assert_eq!(file, "a.md");
+        assert!(message.starts_with("invalid front matter, toc_depth: invalid type"));
+        assert_eq!(span.unwrap().line, 3);
+
+        // out of range for a depth
+        let content = "---\ntoc_depth: -1\n---\n";
+        assert!(matches!(
+            split_front_matter("a.md", content),
+            Err(Error::Parse { .. })
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+
+/// a commit of the local repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    pub email: String,
+    /// seconds since the unix epoch
+    pub time: i64,
+    pub subject: String,
+}
+
+/// what the local repository knows about a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct GitInfo {
+    /// the newest commit which touched the file
+    pub last_commit: Commit,
+    /// everyone who committed to the file, the most active first
+    pub contributors: Vec<String>,
+}
+
+impl GitInfo {
+    /// combined info of several files, e.g. the parts of a merged
+    /// section: the newest commit and the contributors of all of them
+    pub fn merge<'a>(infos: impl IntoIterator<Item = &'a GitInfo>) -> Option<GitInfo> {
+        let mut merged: Option<GitInfo> = None;
+        for info in infos {
+            let Some(merged) = merged.as_mut() else {
+                merged = Some(info.clone());
+                continue;
+            };
+            if info.last_commit.time > merged.last_commit.time {
+                merged.last_commit = info.last_commit.clone();
+            }
+            for contributor in &info.contributors {
+                if !merged.contributors.contains(contributor) {
+                    merged.contributors.push(contributor.clone());
+                }
+            }
+        }
+        merged
+    }
+}
+
+/// code which changed after the note describing it was last committed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct Staleness {
+    /// commits to the covered paths since the last commit of the note
+    pub commits: usize,
+    /// lines added and removed by those commits
+    pub lines: usize,
+    /// the covered paths which changed, in the order they are covered
+    pub paths: Vec<String>,
+}
+
+impl Staleness {
+    /// e.g. "1 commit" or "3 commits"
+    pub fn commits_label(&self) -> String {
+        match self.commits {
+            1 => "1 commit".to_owned(),
+            commits => format!("{commits} commits"),
+        }
+    }
+}
+
+/// history of a file, `None` when git is not installed or the file was
+/// never committed
+///
+/// renames are not followed, `--follow` takes small files which look
+/// alike for copies of each other and credits the wrong people
+pub(crate) fn history(file: &Path) -> Option<GitInfo> {
+    let dir = file.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let output = git(
+        dir,
+        &[
+            "log",
+            "--format=%H%x1f%aN%x1f%aE%x1f%at%x1f%s",
+            "--",
+            &file.file_name()?.to_string_lossy(),
+        ],
+    )?;
+
+    let mut commits = output.lines().filter_map(|line| {
+        let mut fields = line.splitn(5, '\x1f');
+        Some(Commit {
+            hash: fields.next()?.to_owned(),
+            author: fields.next()?.to_owned(),
+            email: fields.next()?.to_owned(),
+            time: fields.next()?.parse().ok()?,
+            subject: fields.next().unwrap_or_default().to_owned(),
+        })
+    });
+    let last_commit = commits.next()?;
+
+    // author -> number of commits, in the order they were first seen
+    let mut counts: Vec<(String, usize)> = vec![(last_commit.author.clone(), 1)];
+    for commit in commits {
+        match counts
+            .iter_mut()
+            .find(|(author, _)| *author == commit.author)
+        {
+            Some((_, count)) => *count += 1,
+            None => counts.push((commit.author, 1)),
+        }
+    }
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    Some(GitInfo {
+        last_commit,
+        contributors: counts.into_iter().map(|(author, _)| author).collect(),
+    })
+}
+
+/// how much the covered `paths`, relative to `dir`, changed in the
+/// commits following `since`, `None` when they did not change or git
+/// cannot tell
+pub(crate) fn staleness(dir: &Path, since: &str, paths: &[String]) -> Option<Staleness> {
+    let range = format!("{since}..HEAD");
+    let log = |paths: &[String]| {
+        let mut args = vec!["log", "--format=%x1e%H", "--numstat", &range, "--"];
+        args.extend(paths.iter().map(String::as_str));
+        git(Some(dir), &args)
+    };
+
+    let mut changed = vec![];
+    for path in paths {
+        if !log(std::slice::from_ref(path))?.is_empty() {
+            changed.push(path.clone());
+        }
+    }
+    if changed.is_empty() {
+        return None;
+    }
+
+    // one log over every path, so that covering a directory and a file
+    // inside of it does not count the same change twice
+    let mut commits = 0;
+    let mut lines = 0;
+    for line in log(&changed)?.lines() {
+        if line.starts_with('\x1e') {
+            commits += 1;
+            continue;
+        }
+        // added, removed and path, binary files count as `-`
+        let mut fields = line.split('\t');
+        for count in [fields.next(), fields.next()] {
+            lines += count
+                .and_then(|count| count.parse::<usize>().ok())
+                .unwrap_or(0);
+        }
+    }
+    Some(Staleness {
+        commits,
+        lines,
+        paths: changed,
+    })
+}
+
+/// full hash of the commit a revision points at, `None` when there is
+/// no such commit, e.g. a typo in a tag name
+pub(crate) fn commit_of(dir: &Path, revision: &str) -> Option<String> {
+    let output = git(
+        Some(dir),
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{revision}^{{commit}}"),
+        ],
+    )?;
+    Some(output.trim_end().to_owned())
+}
+
+/// commit time of a revision, in seconds since the unix epoch
+pub(crate) fn commit_time(dir: &Path, revision: &str) -> Option<i64> {
+    let output = git(Some(dir), &["log", "-1", "--format=%ct", revision, "--"])?;
+    output.trim_end().parse().ok()
+}
+
+/// every file below `dir` at a revision, relative to `dir`
+pub(crate) fn files_at(dir: &Path, revision: &str) -> Option<Vec<String>> {
+    let output = git(Some(dir), &["ls-tree", "-r", "-z", "--name-only", revision])?;
+    Some(
+        output
+            .split('\0')
+            .filter(|file| !file.is_empty())
+            .map(str::to_owned)
+            .collect(),
+    )
+}
+
+/// content of a file at a revision, `path` is relative to `dir`
+pub(crate) fn read_at(dir: &Path, revision: &str, path: &str) -> Option<String> {
+    git(Some(dir), &["show", &format!("{revision}:./{path}")])
+}
+
+/// tags matching a glob pattern, e.g. `v*`, the highest version first
+pub(crate) fn tags(dir: &Path, pattern: &str) -> Option<Vec<String>> {
+    let output = git(
+        Some(dir),
+        &["tag", "--list", "--sort=-version:refname", pattern],
+    )?;
+    Some(output.lines().map(str::to_owned).collect())
+}
+
+/// top directory of the repository `dir` is in
+pub(crate) fn toplevel(dir: &Path) -> Option<PathBuf> {
+    let output = git(Some(dir), &["rev-parse", "--show-toplevel"])?;
+    Some(PathBuf::from(output.trim_end()))
+}
+
+/// author of every line of a file, from the local repository, `None`
+/// when git is not installed or the file is not tracked
+///
+/// lines which are not committed yet have no author
+pub(crate) fn blame(file: &Path) -> Option<Vec<Option<String>>> {
+    let dir = file.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let output = git(
+        dir,
+        &[
+            "blame",
+            "--line-porcelain",
+            "--",
+            &file.file_name()?.to_string_lossy(),
+        ],
+    )?;
+
+    let mut authors = vec![];
+    let mut uncommitted = false;
+    for line in output.lines() {
+        if line.starts_with("0000000000000000000000000000000000000000 ") {
+            uncommitted = true;
+        } else if let Some(author) = line.strip_prefix("author ") {
+            authors.push((!uncommitted).then(|| author.to_owned()));
+            uncommitted = false;
+        }
+    }
+    Some(authors)
+}
+
+/// stdout of a git command run inside `dir`, `None` when it fails
+fn git(dir: Option<&Path>, args: &[&str]) -> Option<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let output = command.args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
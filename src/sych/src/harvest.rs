@@ -0,0 +1,272 @@
+use std::path::{Component, Path, PathBuf};
+
+use markdown::{Block, ListItem, Span};
+
+use crate::include::{declaration, item_end};
+
+/// items which hold documented members, e.g. the methods of an impl
+/// block or the fields of a struct
+const CONTAINERS: &[&str] = &["impl", "trait", "mod", "struct", "enum", "union"];
+
+/// a rust file whose doc comments become a section
+#[derive(Debug)]
+pub(crate) struct RustFile {
+    pub path: PathBuf,
+    /// module path used as the title of the section, e.g. "sych::project"
+    pub module: String,
+}
+
+/// every `.rs` file of the harvested directories, directories named
+/// `target` are skipped
+pub(crate) fn rust_files(path: &Path) -> Vec<RustFile> {
+    if path.is_file() {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        return vec![RustFile {
+            path: path.to_path_buf(),
+            module: module_path(dir, path),
+        }];
+    }
+
+    let mut files: Vec<String> = rust_search::SearchBuilder::default()
+        .location(path)
+        .ext("rs")
+        .build()
+        .filter(|file| {
+            !Path::new(file)
+                .components()
+                .any(|c| c == Component::Normal("target".as_ref()))
+        })
+        .collect();
+    files.sort();
+    files
+        .into_iter()
+        .map(|file| RustFile {
+            module: module_path(path, Path::new(&file)),
+            path: PathBuf::from(file),
+        })
+        .collect()
+}
+
+/// "sych::project" for `src/sych/src/project.rs` harvested from
+/// `src/sych/src`, the crate is named after the directory holding `src`
+fn module_path(dir: &Path, file: &Path) -> String {
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let crate_dir = match dir.file_name() {
+        Some(name) if name == "src" => dir.parent().unwrap_or(&dir),
+        _ => &dir,
+    };
+    let crate_name = crate_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().replace('-', "_"))
+        .unwrap_or_else(|| "crate".to_owned());
+
+    let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    let relative = file.strip_prefix(&dir).unwrap_or(&file).with_extension("");
+    let mut segments: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if segments.last().is_some_and(|last| last == "mod")
+        || (segments.len() == 1 && ["lib", "main"].contains(&segments[0].as_str()))
+    {
+        segments.pop();
+    }
+
+    std::iter::once(crate_name)
+        .chain(segments)
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// the blocks of the section made from the doc comments of a rust file,
+/// `None` when it has none
+///
+/// module docs (`//!`) come first, followed by a heading for every
+/// documented item with its path and line, and the `// SYCH:` notes
+/// when `notes` is set
+pub(crate) fn harvest(module: &str, label: &str, content: &str, notes: bool) -> Option<Vec<Block>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut module_docs = String::new();
+    // item path, 1-based line, docs
+    let mut items: Vec<(String, usize, String)> = vec![];
+    let mut found_notes: Vec<(usize, &str)> = vec![];
+    // name and last line of the items the current line is inside of
+    let mut containers: Vec<(String, usize)> = vec![];
+    let mut pending = String::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        containers.retain(|(_, end)| *end >= i);
+        let trimmed = lines[i].trim_start();
+        if let Some(doc) = trimmed.strip_prefix("//!") {
+            push_doc_line(&mut module_docs, doc);
+        } else if let Some(doc) = trimmed
+            .strip_prefix("///")
+            .filter(|_| !trimmed.starts_with("////"))
+        {
+            push_doc_line(&mut pending, doc);
+        } else if let Some(note) = trimmed.strip_prefix("// SYCH:").filter(|_| notes) {
+            found_notes.push((i + 1, note.trim()));
+        } else if trimmed.starts_with("#[") || trimmed.starts_with("#![") {
+            // attributes sit between the docs and the item, they can
+            // span several lines
+            let mut depth = 0i32;
+            while i < lines.len() {
+                depth += lines[i].matches('[').count() as i32;
+                depth -= lines[i].matches(']').count() as i32;
+                if depth <= 0 {
+                    break;
+                }
+                i += 1;
+            }
+        } else if !trimmed.is_empty() && !trimmed.starts_with("//") {
+            let declared = declaration(lines[i]);
+            if !pending.is_empty() {
+                let name = declared
+                    .map(|(_, name)| name)
+                    .or_else(|| member_name(trimmed).filter(|_| !containers.is_empty()));
+                if let Some(name) = name {
+                    let path = containers
+                        .iter()
+                        .map(|(container, _)| container.as_str())
+                        .chain([name])
+                        .collect::<Vec<_>>()
+                        .join("::");
+                    items.push((path, i + 1, std::mem::take(&mut pending)));
+                }
+                pending.clear();
+            }
+            if let Some((_, name)) = declared.filter(|(keyword, _)| CONTAINERS.contains(keyword)) {
+                if let Some(end) = item_end(&lines, i, lines.len()).filter(|end| *end > i) {
+                    containers.push((name.to_owned(), end));
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if module_docs.trim().is_empty() && items.is_empty() && found_notes.is_empty() {
+        return None;
+    }
+
+    let mut blocks = vec![Block::Header(vec![Span::Text(module.to_owned())], 2)];
+    blocks.extend(doc_blocks(&module_docs, 2));
+    for (path, line, docs) in items {
+        blocks.push(Block::Header(vec![Span::Code(path)], 3));
+        blocks.push(Block::Paragraph(vec![Span::Code(format!(
+            "{label}:{line}"
+        ))]));
+        blocks.extend(doc_blocks(&docs, 3));
+    }
+    if !found_notes.is_empty() {
+        blocks.push(Block::Header(vec![Span::Text("Notes".to_owned())], 3));
+        blocks.push(Block::UnorderedList(
+            found_notes
+                .into_iter()
+                .map(|(line, note)| {
+                    ListItem::Simple(vec![
+                        Span::Code(format!("{label}:{line}")),
+                        Span::Text(format!(" {note}")),
+                    ])
+                })
+                .collect(),
+        ));
+    }
+    Some(blocks)
+}
+
+/// a line of a doc comment without the space following `///` or `//!`
+fn push_doc_line(docs: &mut String, line: &str) {
+    docs.push_str(line.strip_prefix(' ').unwrap_or(line));
+    docs.push('\n');
+}
+
+/// name of a field or variant, e.g. `pub root: Option<String>,`
+fn member_name(line: &str) -> Option<&str> {
+    let line = match line.strip_prefix("pub") {
+        Some(after) if after.starts_with('(') => after.split_once(')')?.1.trim_start(),
+        Some(after) if after.starts_with(' ') => after.trim_start(),
+        _ => line,
+    };
+    let end = line
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(line.len());
+    let name = &line[..end];
+    let follows = line[end..].trim_start().chars().next();
+    (!name.is_empty() && matches!(follows, None | Some(':' | '(' | '{' | ',' | '=')))
+        .then_some(name)
+}
+
+/// markdown of a doc comment with its headings moved below `level`,
+/// so that a `# Examples` does not start a section of its own
+fn doc_blocks(docs: &str, level: usize) -> Vec<Block> {
+    let mut blocks = markdown::tokenize(docs);
+    demote(&mut blocks, level);
+    blocks
+}
+
+/// fences rustdoc treats as rust, the ones without a language included
+const RUSTDOC_ATTRIBUTES: &[&str] = &[
+    "rust",
+    "ignore",
+    "no_run",
+    "should_panic",
+    "compile_fail",
+    "edition2015",
+    "edition2018",
+    "edition2021",
+    "edition2024",
+];
+
+/// moves the headings below `level` and turns rustdoc fences into rust
+/// ones without the lines rustdoc hides
+fn demote(blocks: &mut [Block], level: usize) {
+    for block in blocks {
+        match block {
+            Block::Header(_, size) => *size = (*size + level).min(6),
+            Block::CodeBlock(meta, code) => {
+                let is_rust = meta.as_deref().is_none_or(|meta| {
+                    meta.split([',', ' '])
+                        .filter(|attribute| !attribute.is_empty())
+                        .all(|attribute| RUSTDOC_ATTRIBUTES.contains(&attribute))
+                });
+                if is_rust {
+                    *meta = Some("rust".to_owned());
+                    *code = code
+                        .lines()
+                        .filter(|line| *line != "#" && !line.starts_with("# "))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                }
+            }
+            Block::Blockquote(inner) => demote(inner, level),
+            Block::OrderedList(items, _) | Block::UnorderedList(items) => {
+                for item in items {
+                    if let ListItem::Paragraph(inner) = item {
+                        demote(inner, level);
+                    }
+                }
+            }
+            _ => continue,
+        }
+    }
+}
+
+/// path of `file` relative to the directory `from`, both are resolved
+/// first so that they can live anywhere
+pub(crate) fn relative_to(from: &Path, file: &Path) -> String {
+    let (Ok(from), Ok(file)) = (from.canonicalize(), file.canonicalize()) else {
+        return file.to_string_lossy().into_owned();
+    };
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = file.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    relative.to_string_lossy().into_owned()
+}
@@ -0,0 +1,503 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use indexmap::IndexMap;
+use markdown::Block;
+use syntect::highlighting::ThemeSet;
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use crate::error::SourceSpan;
+use crate::indexer::Section;
+use crate::transform::for_each_block;
+use crate::Error;
+
+const CODE_THEME: &str = "InspiredGitHub";
+
+/// attribute added to the fence of a resolved snippet, it holds the
+/// file and the lines the snippet was taken from, e.g. "src/cli.rs:40-60"
+const SOURCE_ATTRIBUTE: &str = "source=";
+
+/// which part of a file a fence includes
+#[derive(Debug)]
+enum Selector {
+    Whole,
+    /// 1-based and inclusive, `None` runs to the end of the file
+    Lines(usize, Option<usize>),
+    /// lines between `region: name` and `endregion: name` comments
+    Region(String),
+    /// a rust item path, e.g. `SychCLI::execute`
+    Item(String),
+}
+
+/// a code fence pulling its content from a file of the project, e.g.
+/// `rust file=src/cli.rs lines=40-60`, with `region=setup` or
+/// `item=SychCLI::execute` in place of `lines=`
+#[derive(Debug)]
+struct Include {
+    file: String,
+    selector: Selector,
+    /// the attribute choosing the selector, as written in the fence
+    attribute: String,
+}
+
+/// fills every code fence with a `file=` attribute with the snippet it
+/// points at, `base` is the directory file paths are relative to and
+/// `read` gives the content of such a path, `markdown` holds the name
+/// and content every source was indexed from
+///
+/// every fence is resolved even when one of them fails so that all
+/// broken snippets are reported at once
+pub(crate) fn resolve_includes(
+    sections: &mut IndexMap<String, Section>,
+    base: &Path,
+    markdown: &HashMap<String, (String, String)>,
+    read: &dyn Fn(&str) -> Option<String>,
+) -> Vec<BrokenSnippet> {
+    let mut files: HashMap<String, Option<String>> = HashMap::new();
+    let mut errors = vec![];
+    for section in sections.values_mut() {
+        for part in &mut section.parts {
+            for_each_block(&mut part.blocks, &mut |block| {
+                let Block::CodeBlock(Some(meta), code) = block else {
+                    return;
+                };
+                let include = match parse(meta) {
+                    Some(Ok(include)) => include,
+                    Some(Err(problem)) => {
+                        errors.push(error(markdown, &part.source, meta, "", problem));
+                        return;
+                    }
+                    None => return,
+                };
+                let content = files
+                    .entry(include.file.clone())
+                    .or_insert_with(|| read(&include.file));
+                let Some(content) = content else {
+                    let problem = format!(
+                        "included file {} does not exist",
+                        base.join(&include.file).display()
+                    );
+                    let attribute = format!("file={}", include.file);
+                    errors.push(error(markdown, &part.source, meta, &attribute, problem));
+                    return;
+                };
+                match select(content, &include) {
+                    Ok((start, end, snippet)) => {
+                        *code = snippet;
+                        let lines = if start == end {
+                            start.to_string()
+                        } else {
+                            format!("{start}-{end}")
+                        };
+                        meta.push_str(&format!(" {SOURCE_ATTRIBUTE}{}:{lines}", include.file));
+                    }
+                    Err(problem) => {
+                        let problem = format!("{}, in {}", problem, include.file);
+                        errors.push(error(
+                            markdown,
+                            &part.source,
+                            meta,
+                            &include.attribute,
+                            problem,
+                        ));
+                    }
+                }
+            });
+        }
+    }
+    errors
+}
+
+/// `None` when the fence does not include anything
+fn parse(meta: &str) -> Option<Result<Include, String>> {
+    let mut file = None;
+    let mut selectors = vec![];
+    for attribute in meta.split_whitespace().skip(1) {
+        let Some((key, value)) = attribute.split_once('=') else {
+            continue;
+        };
+        let selector = match key {
+            "file" => {
+                file = Some(value.to_owned());
+                continue;
+            }
+            "lines" => match parse_lines(value) {
+                Some((start, end)) => Selector::Lines(start, end),
+                None => {
+                    return Some(Err(format!(
+                        "invalid line range \"{value}\", expected e.g. 40-60, 40 or 40-"
+                    )))
+                }
+            },
+            "region" => Selector::Region(value.to_owned()),
+            "item" => Selector::Item(value.to_owned()),
+            _ => continue,
+        };
+        selectors.push((selector, attribute.to_owned()));
+    }
+
+    let file = file?;
+    if selectors.len() > 1 {
+        return Some(Err(
+            "a snippet is chosen by only one of lines=, region= or item=".to_owned(),
+        ));
+    }
+    let (selector, attribute) = selectors
+        .pop()
+        .unwrap_or((Selector::Whole, format!("file={file}")));
+    Some(Ok(Include {
+        file,
+        selector,
+        attribute,
+    }))
+}
+
+fn parse_lines(value: &str) -> Option<(usize, Option<usize>)> {
+    let (start, end) = match value.split_once('-') {
+        Some((start, "")) => (start.parse().ok()?, None),
+        Some((start, end)) => (start.parse().ok()?, Some(end.parse().ok()?)),
+        None => {
+            let line = value.parse().ok()?;
+            (line, Some(line))
+        }
+    };
+    if start == 0 || end.is_some_and(|end| end < start) {
+        return None;
+    }
+    Some((start, end))
+}
+
+/// the first and last line of the snippet along with its text
+fn select(content: &str, include: &Include) -> Result<(usize, usize, String), String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (start, end) = match &include.selector {
+        Selector::Whole => (1, lines.len()),
+        Selector::Lines(start, end) => {
+            // `lines=50-` runs to the end, which may come before line 50
+            if *start > lines.len() || end.is_some_and(|end| end > lines.len()) {
+                let end = end.map(|end| end.to_string()).unwrap_or_default();
+                return Err(format!(
+                    "lines {start}-{end} do not exist, the file has {} lines",
+                    lines.len()
+                ));
+            }
+            (*start, end.unwrap_or(lines.len()))
+        }
+        Selector::Region(name) => find_region(&lines, name)?,
+        Selector::Item(path) => {
+            let stem = Path::new(&include.file)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            find_item(&lines, &stem, path)?
+        }
+    };
+
+    let snippet: Vec<&str> = lines[start - 1..end]
+        .iter()
+        .copied()
+        .filter(|line| region_marker(line).is_none())
+        .collect();
+    Ok((start, end, dedent(&snippet)))
+}
+
+/// name of the region a `region:` or `endregion:` comment starts or ends
+fn region_marker(line: &str) -> Option<(bool, &str)> {
+    let trimmed = line.trim_start();
+    let comment = ["//", "#", "--", "/*", "<!--"]
+        .iter()
+        .find_map(|start| trimmed.strip_prefix(start))?
+        .trim_start();
+    let (is_start, rest) = match comment.strip_prefix("endregion:") {
+        Some(rest) => (false, rest),
+        None => (true, comment.strip_prefix("region:")?),
+    };
+    let name = rest.trim().trim_end_matches("*/").trim_end_matches("-->");
+    Some((is_start, name.trim()))
+}
+
+fn find_region(lines: &[&str], name: &str) -> Result<(usize, usize), String> {
+    let marker = |is_start: bool| {
+        lines
+            .iter()
+            .position(|line| region_marker(line) == Some((is_start, name)))
+    };
+    match (marker(true), marker(false)) {
+        (Some(start), Some(end)) if start < end => Ok((start + 2, end)),
+        (Some(_), _) => Err(format!("region \"{name}\" has no `endregion: {name}`")),
+        (None, _) => Err(format!("region \"{name}\" does not exist")),
+    }
+}
+
+/// lines of a rust item, with its doc comments and attributes, found
+/// by its path inside the file, e.g. `SychCLI::execute` for a method
+/// of an impl block, the name of the file may start the path
+fn find_item(lines: &[&str], stem: &str, path: &str) -> Result<(usize, usize), String> {
+    let mut segments: Vec<&str> = path.split("::").collect();
+    while segments.len() > 1 && ["crate", "self", stem].contains(&segments[0]) {
+        segments.remove(0);
+    }
+    find_in(lines, 0, lines.len(), &segments)
+        .map(|(start, end)| (start + 1, end + 1))
+        .ok_or_else(|| format!("item {path} does not exist"))
+}
+
+/// 0-based first and last line of the item inside `lines[from..to]`,
+/// every item matching the first segment is tried since a type can
+/// have several impl blocks
+fn find_in(lines: &[&str], from: usize, to: usize, segments: &[&str]) -> Option<(usize, usize)> {
+    let (name, rest) = segments.split_first()?;
+    let mut line = from;
+    while line < to {
+        if declares(lines[line], name) {
+            let end = item_end(lines, line, to)?;
+            let found = if rest.is_empty() {
+                Some((item_start(lines, line, from), end))
+            } else {
+                find_in(lines, line + 1, end, rest)
+            };
+            if found.is_some() {
+                return found;
+            }
+            line = end;
+        }
+        line += 1;
+    }
+    None
+}
+
+/// whether the line starts the declaration of an item named `name`
+fn declares(line: &str, name: &str) -> bool {
+    declaration(line).is_some_and(|(_, declared)| declared == name)
+}
+
+/// keyword and name of the item the line declares, impl blocks are
+/// named after the type they are for
+pub(crate) fn declaration(line: &str) -> Option<(&'static str, &str)> {
+    let mut rest = line.trim_start();
+    if let Some(after) = rest.strip_prefix("pub") {
+        rest = match after.strip_prefix('(') {
+            Some(scope) => scope.split_once(')').map(|(_, r)| r).unwrap_or(""),
+            None if after.starts_with(char::is_whitespace) => after,
+            None => return None,
+        }
+        .trim_start();
+    }
+    // `const fn` is a function, a `const` on its own is an item
+    while let Some(after) = [
+        "default ",
+        "async ",
+        "unsafe ",
+        "extern \"C\" ",
+        "const fn ",
+    ]
+    .iter()
+    .find_map(|qualifier| rest.strip_prefix(qualifier))
+    {
+        rest = if rest.starts_with("const fn ") {
+            &rest["const ".len()..]
+        } else {
+            after.trim_start()
+        };
+    }
+
+    if let Some(after) = rest.strip_prefix("impl") {
+        let header = match after.strip_prefix('<') {
+            Some(_) => skip_generics(after),
+            None if after.starts_with(char::is_whitespace) => after.trim_start(),
+            None => return None,
+        };
+        // the type is the last path before any generics or the body,
+        // after `for` when it is a trait implementation
+        let header = header.split('{').next().unwrap_or_default();
+        let header = header.split(" where").next().unwrap_or_default();
+        let ty = header.rsplit(" for ").next().unwrap_or_default().trim();
+        let ty = ty.split('<').next().unwrap_or_default();
+        let name = ty.rsplit("::").next().unwrap_or_default().trim();
+        return (!name.is_empty()).then_some(("impl", name));
+    }
+
+    let (keyword, after) = rest.split_once(char::is_whitespace)?;
+    let keyword = [
+        "fn",
+        "struct",
+        "enum",
+        "trait",
+        "mod",
+        "type",
+        "const",
+        "static",
+        "union",
+        "macro_rules!",
+    ]
+    .into_iter()
+    .find(|known| *known == keyword)?;
+    let after = after.trim_start();
+    let ident_end = after
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(after.len());
+    (ident_end > 0).then_some((keyword, &after[..ident_end]))
+}
+
+/// what follows `<...>`, nested brackets included
+fn skip_generics(text: &str) -> &str {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return text[i + 1..].trim_start();
+                }
+            }
+            _ => continue,
+        }
+    }
+    ""
+}
+
+/// 0-based line the item starting at `line` ends on, either its closing
+/// brace or the `;` of items without a body
+pub(crate) fn item_end(lines: &[&str], line: usize, to: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut opened = false;
+    for (i, text) in lines.iter().enumerate().take(to).skip(line) {
+        let mut chars = text.chars().peekable();
+        let mut in_string = false;
+        while let Some(c) = chars.next() {
+            if in_string {
+                match c {
+                    '\\' => {
+                        chars.next();
+                    }
+                    '"' => in_string = false,
+                    _ => (),
+                }
+                continue;
+            }
+            match c {
+                '"' => in_string = true,
+                '/' if chars.peek() == Some(&'/') => break,
+                // a char literal, not a brace
+                '\'' if matches!(chars.clone().nth(1), Some('\'')) => {
+                    chars.next();
+                    chars.next();
+                }
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => {
+                    depth -= 1;
+                    if opened && depth == 0 {
+                        return Some(i);
+                    }
+                }
+                ';' if !opened && depth == 0 => return Some(i),
+                _ => (),
+            }
+        }
+    }
+    None
+}
+
+/// first line of the doc comments and attributes above the item
+fn item_start(lines: &[&str], line: usize, from: usize) -> usize {
+    let mut start = line;
+    while start > from {
+        let above = lines[start - 1].trim_start();
+        if above.starts_with("///") || above.starts_with("#[") || above.starts_with("//!") {
+            start -= 1;
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+/// removes the indentation every line has in common
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut snippet = String::new();
+    for line in lines {
+        snippet.push_str(line.get(indent..).unwrap_or("").trim_end());
+        snippet.push('\n');
+    }
+    snippet
+}
+
+/// where a resolved snippet comes from, e.g. "src/cli.rs:40-60"
+pub(crate) fn snippet_source(meta: &str) -> Option<&str> {
+    meta.split_whitespace()
+        .find_map(|attribute| attribute.strip_prefix(SOURCE_ATTRIBUTE))
+}
+
+/// the snippet highlighted with inline styles, so that it looks the
+/// same in every output without a stylesheet
+pub(crate) fn highlight(lang: &str, code: &str) -> String {
+    static HIGHLIGHTING: OnceLock<(SyntaxSet, ThemeSet)> = OnceLock::new();
+    let (syntaxes, themes) = HIGHLIGHTING.get_or_init(|| {
+        (
+            SyntaxSet::load_defaults_newlines(),
+            ThemeSet::load_defaults(),
+        )
+    });
+    let syntax = syntaxes
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    highlighted_html_for_string(code, syntaxes, syntax, &themes.themes[CODE_THEME])
+        .unwrap_or_else(|_| format!("<pre>{code}</pre>"))
+}
+
+/// a code fence whose snippet cannot be included, `sych check` reports
+/// it and every build fails with it
+#[derive(Debug, Clone)]
+pub(crate) struct BrokenSnippet {
+    /// markdown file of the fence, relative to the root
+    pub source: String,
+    pub message: String,
+    pub span: Option<SourceSpan>,
+}
+
+impl BrokenSnippet {
+    pub fn error(&self) -> Error {
+        Error::Discovery {
+            message: self.message.clone(),
+            span: self.span.clone(),
+        }
+    }
+}
+
+/// points at the attribute of the fence which could not be resolved
+fn error(
+    markdown: &HashMap<String, (String, String)>,
+    source: &str,
+    meta: &str,
+    attribute: &str,
+    message: String,
+) -> BrokenSnippet {
+    let span = markdown.get(source).and_then(|(file, content)| {
+        let fence = content.find(&format!("```{meta}"))?;
+        let offset = meta.find(attribute).unwrap_or(0);
+        let start = fence + 3 + offset;
+        let width = if attribute.is_empty() {
+            meta.len()
+        } else {
+            attribute.len()
+        };
+        Some(SourceSpan::new(file, content, start..start + width))
+    });
+    BrokenSnippet {
+        source: source.to_owned(),
+        message,
+        span,
+    }
+}
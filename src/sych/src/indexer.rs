@@ -0,0 +1,210 @@
+use indexmap::IndexMap;
+use markdown::{Block, Span};
+
+use crate::frontmatter::FrontMatter;
+use crate::git::{GitInfo, Staleness};
+use crate::{MergeStrategy, SychConfig};
+
+/// all the content of a `##` section, in the order in which the
+/// files were encountered
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Section {
+    pub parts: Vec<SectionPart>,
+}
+
+/// content of a section which comes from a single markdown file
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SectionPart {
+    /// the `##` title as written in the file
+    pub title: String,
+    pub source: String,
+    pub front_matter: FrontMatter,
+    pub blocks: Vec<Block>,
+    /// history of the file in the local repository, `None` outside of
+    /// one or when `[doc] git` is off
+    pub git: Option<GitInfo>,
+    /// set when the code the file covers changed by more than
+    /// `[check] stale_threshold` lines since the file was last committed
+    pub stale: Option<Staleness>,
+}
+
+impl SectionPart {
+    /// sub-heading with the path of the file this part came from
+    pub fn origin_heading(&self) -> Block {
+        Block::Header(vec![Span::Text(self.source.clone())], 3)
+    }
+}
+
+/// a `##` title which was declared by more than one file
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Collision {
+    pub title: String,
+    pub sources: Vec<String>,
+    pub strategy: MergeStrategy,
+}
+
+impl Section {
+    /// true when more than one file contributed to this section
+    pub fn is_merged(&self) -> bool {
+        self.parts.len() > 1
+    }
+
+    /// last commit and contributors of all the files of the section
+    pub fn git(&self) -> Option<GitInfo> {
+        GitInfo::merge(self.parts.iter().filter_map(|part| part.git.as_ref()))
+    }
+}
+
+/// adds the sections of a single file to the index and records every
+/// title that was already declared by another file
+pub(crate) fn merge_file_index(
+    sych_cfg: &SychConfig,
+    source: &str,
+    front_matter: &FrontMatter,
+    file_index: IndexMap<String, Vec<Block>>,
+    docs_index: &mut IndexMap<String, Section>,
+    collisions: &mut Vec<Collision>,
+) {
+    for (title, blocks) in file_index {
+        let part = SectionPart {
+            title: title.clone(),
+            source: source.to_owned(),
+            front_matter: front_matter.clone(),
+            blocks,
+            git: None,
+            stale: None,
+        };
+        let strategy = sych_cfg.merge_strategy(&title);
+        match collisions.iter_mut().find(|c| c.title == title) {
+            Some(collision) => collision.sources.push(source.to_owned()),
+            None if docs_index.contains_key(&title) => collisions.push(Collision {
+                title: title.clone(),
+                sources: vec![
+                    docs_index[&title].parts[0].source.clone(),
+                    source.to_owned(),
+                ],
+                strategy,
+            }),
+            None => {
+                // first time anyone declares this title
+                docs_index.insert(title, Section { parts: vec![part] });
+                continue;
+            }
+        }
+
+        match strategy {
+            MergeStrategy::Merge | MergeStrategy::Error => {
+                docs_index.entry(title).or_default().parts.push(part);
+            }
+            MergeStrategy::Separate => {
+                // rename the first occurence once we know it collides
+                if let Some(index) = docs_index.get_index_of(&title) {
+                    let (_, first) = docs_index.shift_remove_index(index).unwrap();
+                    let first_title = separate_title(&title, &first.parts[0].source, docs_index);
+                    docs_index.shift_insert(index, first_title, first);
+                }
+                let title = separate_title(&title, source, docs_index);
+                docs_index.insert(title, Section { parts: vec![part] });
+            }
+        }
+    }
+}
+
+/// `title (source)`, numbered when a file already declares that exact
+/// title so that neither section replaces the other
+fn separate_title(title: &str, source: &str, docs_index: &IndexMap<String, Section>) -> String {
+    let separate = format!("{title} ({source})");
+    if !docs_index.contains_key(&separate) {
+        return separate;
+    }
+    (2..)
+        .map(|n| format!("{title} ({source}, {n})"))
+        .find(|numbered| !docs_index.contains_key(numbered))
+        .unwrap()
+}
+
+pub(crate) fn create_index(blocks: Vec<Block>, docs_index: &mut IndexMap<String, Vec<Block>>) {
+    let mut doc_section = String::new();
+    // println!("Values: {:?}", blocks);
+    for block in blocks {
+        match block {
+            Block::OrderedList(li, li_type) => {
+                if !doc_section.is_empty() {
+                    docs_index
+                        .entry(doc_section.clone())
+                        .and_modify(|e| e.push(Block::OrderedList(li, li_type)));
+                    continue;
+                }
+            }
+            Block::CodeBlock(copt, code) => {
+                if !doc_section.is_empty() {
+                    docs_index
+                        .entry(doc_section.clone())
+                        .and_modify(|e| e.push(Block::CodeBlock(copt, code)));
+                    continue;
+                }
+            }
+            Block::Paragraph(pspans) => {
+                if !doc_section.is_empty() {
+                    docs_index
+                        .entry(doc_section.clone())
+                        .and_modify(|e| e.push(Block::Paragraph(pspans.clone())));
+                    continue;
+                }
+                continue;
+            }
+            Block::Header(h, hsize) => {
+                // println!("header : {h:?}");
+
+                if hsize == 1_usize {
+                    continue;
+                }
+
+                // TODO: check this part
+                if hsize == 2_usize && h.len() != 1 {
+                    return;
+                }
+                if !doc_section.is_empty() && hsize > 2_usize {
+                    docs_index
+                        .entry(doc_section.clone())
+                        .and_modify(|e| e.push(Block::Header(h.clone(), hsize)));
+                    continue;
+                }
+                if hsize == 2_usize {
+                    if let Some(header) = h.first() {
+                        match header {
+                            markdown::Span::Text(t) => {
+                                doc_section = t.to_owned();
+                                docs_index.entry(t.to_owned()).or_default();
+                            }
+                            _ => {
+                                return;
+                            }
+                        }
+                    }
+                    continue;
+                }
+            }
+            Block::Blockquote(bq) => {
+                if !doc_section.is_empty() {
+                    docs_index
+                        .entry(doc_section.clone())
+                        .and_modify(|e| e.push(Block::Blockquote(bq)));
+                    continue;
+                }
+            }
+            Block::UnorderedList(items) => {
+                if !doc_section.is_empty() {
+                    docs_index
+                        .entry(doc_section.clone())
+                        .and_modify(|e| e.push(Block::UnorderedList(items)));
+                    continue;
+                }
+            }
+            _ => continue,
+        }
+    }
+}
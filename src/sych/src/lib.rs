@@ -0,0 +1,301 @@
+//! sych turns the markdown notes of a project into docs, the `sych`
+//! binary is a thin layer on top of this crate.
+//!
+//! ```no_run
+//! let project = sych::Project::builder()
+//!     .config_path("sych.toml")
+//!     .build()?;
+//! let docs = project.index()?;
+//! docs.check_collisions()?;
+//! docs.write_html(&project.root().join(".sych.html"), false)?;
+//! # Ok::<(), sych::Error>(())
+//! ```
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+mod anchor;
+mod changelog;
+mod check;
+mod config;
+mod debt;
+mod epub;
+mod error;
+mod export;
+mod external;
+mod frontmatter;
+mod git;
+mod harvest;
+mod include;
+mod indexer;
+mod man;
+mod owners;
+mod project;
+mod refs;
+mod terminal;
+mod transform;
+mod transpiler;
+
+pub use changelog::{Change, ChangeKind, SYCH_SNAPSHOT};
+pub use check::{Diagnostic, Severity};
+pub use config::{ConfigDiagnostic, SYCH_LOCAL_TOML};
+pub use error::{Error, Result, SourceSpan};
+pub use export::{TextFlavor, TextStats};
+pub use frontmatter::FrontMatter;
+pub use git::{Commit, GitInfo};
+pub use indexer::{Collision, Section, SectionPart};
+pub use man::MAN_SECTION;
+pub use project::{Docs, Project, ProjectBuilder, Sources, SYCH_TOML};
+pub use refs::RefsChange;
+pub use transform::Transform;
+
+/// the parsed markdown the sections are made of
+pub use markdown;
+
+const DEFAULT_TOC_DEPTH: usize = 2;
+const DEFAULT_STALE_THRESHOLD: usize = 50;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Doc {
+    pub root: Option<String>,
+    pub exclude: Option<Vec<String>>,
+    /// what to do when two files declare the same `##` section
+    pub merge: Option<MergeStrategy>,
+    /// number of heading levels below a section which are listed in
+    /// the "on this page" table of contents, 0 disables it
+    pub toc_depth: Option<usize>,
+    /// per-section overrides keyed by the `##` title
+    pub sections: Option<HashMap<String, SectionConfig>>,
+    /// read the last commit and contributors of every file from the
+    /// local repository, on by default
+    pub git: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum MergeStrategy {
+    /// append the content under one section with a sub-heading per file
+    #[default]
+    Merge,
+    /// keep one section per file, disambiguated by the file path
+    Separate,
+    /// refuse to build when a collision is found
+    Error,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SectionConfig {
+    pub merge: Option<MergeStrategy>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct Meta {
+    /// defaults to the name of the directory of sych.toml
+    pub title: String,
+    pub authors: Vec<String>,
+    pub version: String,
+    pub description: String,
+}
+
+impl Default for Meta {
+    fn default() -> Self {
+        Meta {
+            title: String::new(),
+            authors: vec![],
+            version: "0.1.0".to_owned(),
+            description: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ExtensionMeta {
+    pub url: String,
+    /// format of the code fence content, "json" payloads are validated by `sych check`
+    pub payload: Option<String>,
+    pub opts: Option<HashMap<String, String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct CheckConfig {
+    pub external: Option<ExternalCheckConfig>,
+    /// lines of covered code which may change before a note is flagged
+    /// as possibly outdated
+    pub stale_threshold: Option<usize>,
+}
+
+/// settings of `sych check --external`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ExternalCheckConfig {
+    /// number of urls requested at the same time
+    pub concurrency: Option<usize>,
+    /// seconds before a request is given up
+    pub timeout: Option<u64>,
+    /// maximum requests per second sent to the same host
+    pub rate_limit: Option<f64>,
+    /// only urls matching one of these patterns are checked
+    pub allow: Option<Vec<String>>,
+    /// urls matching one of these patterns are never checked
+    pub ignore: Option<Vec<String>>,
+    /// hours for which a successful result is reused
+    pub cache_ttl: Option<u64>,
+}
+
+/// settings of the multi-page `site` output
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SiteConfig {
+    /// absolute url the site is published at, used for sitemap.xml
+    pub base_url: Option<String>,
+}
+
+/// rust files whose doc comments are indexed next to the markdown files
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct HarvestConfig {
+    /// directories or files, relative to sych.toml
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// `// SYCH:` comments are indexed as notes of their module
+    pub notes: Option<bool>,
+}
+
+/// the generated section listing TODO, FIXME and HACK comments
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct DebtConfig {
+    /// globs of the scanned files, relative to sych.toml
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// tags starting the comments, TODO, FIXME and HACK by default
+    pub tags: Option<Vec<String>>,
+    pub group_by: Option<DebtGrouping>,
+    /// title of the section, "Technical debt" by default
+    pub title: Option<String>,
+    /// link to a line of a file, e.g. a repository browser, `{path}`
+    /// and `{line}` are replaced
+    pub url: Option<String>,
+    /// authors are read with git blame, on by default
+    pub blame: Option<bool>,
+}
+
+/// what the comments of the debt section are grouped by
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum DebtGrouping {
+    #[default]
+    File,
+    /// the directory of the file
+    Directory,
+    Tag,
+    Author,
+}
+
+/// the generated "who knows what" section
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct OwnersConfig {
+    /// title of the section, "Who knows what" by default
+    pub title: Option<String>,
+    /// CODEOWNERS file relative to sych.toml, by default the one of the
+    /// repository is looked up in `.github/`, the top and `docs/`
+    pub codeowners: Option<String>,
+}
+
+/// the generated "what changed" section and its atom feed
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ChangelogConfig {
+    /// revision the docs are compared with, by default the snapshot left
+    /// by the last build
+    pub since: Option<String>,
+    /// revision compared with `since`, by default the working tree
+    pub until: Option<String>,
+    /// title of the section, "What changed" by default
+    pub title: Option<String>,
+}
+
+/// the versions `sych build --versions` builds next to each other
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct VersionsConfig {
+    /// glob pattern of the git tags built, every tag by default
+    pub tags: Option<String>,
+    /// label of the working tree, "latest" by default
+    pub current: Option<String>,
+}
+
+/// a `[[transforms]]` entry enabling a [`Transform`]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct TransformConfig {
+    pub name: String,
+    /// every other key of the entry
+    #[serde(flatten)]
+    pub options: toml::Table,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SychConfig {
+    #[serde(default)]
+    pub meta: Meta,
+    pub doc: Option<Doc>,
+    /// markdown files making up the docs, in order, instead of every
+    /// file found inside the root
+    pub refs: Option<Vec<String>>,
+    pub extensions: Option<HashMap<String, ExtensionMeta>>,
+    pub check: Option<CheckConfig>,
+    pub site: Option<SiteConfig>,
+    /// passes run over the indexed docs before rendering, in order
+    pub transforms: Option<Vec<TransformConfig>>,
+    pub harvest: Option<HarvestConfig>,
+    pub debt: Option<DebtConfig>,
+    pub owners: Option<OwnersConfig>,
+    pub changelog: Option<ChangelogConfig>,
+    pub versions: Option<VersionsConfig>,
+}
+
+impl SychConfig {
+    pub fn toc_depth(&self) -> usize {
+        self.doc
+            .as_ref()
+            .and_then(|doc| doc.toc_depth)
+            .unwrap_or(DEFAULT_TOC_DEPTH)
+    }
+
+    pub fn git_metadata(&self) -> bool {
+        self.doc.as_ref().and_then(|doc| doc.git).unwrap_or(true)
+    }
+
+    pub fn stale_threshold(&self) -> usize {
+        self.check
+            .as_ref()
+            .and_then(|check| check.stale_threshold)
+            .unwrap_or(DEFAULT_STALE_THRESHOLD)
+    }
+
+    /// merge strategy for the given section title, section overrides
+    /// take precedence over the `[doc]` wide strategy
+    pub fn merge_strategy(&self, title: &str) -> MergeStrategy {
+        let Some(doc) = self.doc.as_ref() else {
+            return MergeStrategy::default();
+        };
+        doc.sections
+            .as_ref()
+            .and_then(|sections| sections.get(title))
+            .and_then(|section| section.merge)
+            .or(doc.merge)
+            .unwrap_or_default()
+    }
+}
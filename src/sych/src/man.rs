@@ -0,0 +1,240 @@
+use markdown::{Block, ListItem, Span};
+
+use crate::anchor::{Anchors, Link};
+use crate::indexer::Section;
+use crate::Meta;
+
+/// man section for miscellaneous documentation
+pub const MAN_SECTION: &str = "7";
+
+/// placeholder for a line break until the text is split into lines
+const BREAK: &str = "\u{1}br";
+
+/// renders a section as a roff man page, links are numbered and listed
+/// under NOTES the way git's man pages do it
+pub(crate) struct ManRenderer<'a> {
+    anchors: &'a Anchors,
+    source: String,
+    notes: Vec<String>,
+}
+
+impl<'a> ManRenderer<'a> {
+    pub fn new(anchors: &'a Anchors) -> Self {
+        ManRenderer {
+            anchors,
+            source: String::new(),
+            notes: vec![],
+        }
+    }
+
+    pub fn render(mut self, meta: &Meta, title: &str, section: &Section) -> String {
+        let id = &self.anchors.sections[title];
+        let mut out = format!(
+            ".TH \"{}\" \"{MAN_SECTION}\" \"\" \"{} {}\" \"{}\"\n",
+            escape(&id.to_uppercase()),
+            escape(&meta.title),
+            escape(&meta.version),
+            escape(&meta.title)
+        );
+        out.push_str(&format!(".SH NAME\n{} \\- {}\n", escape(id), escape(title)));
+
+        // content before the first heading still needs a section
+        let starts_with_heading = section
+            .parts
+            .first()
+            .and_then(|part| part.blocks.first())
+            .is_some_and(|block| matches!(block, Block::Header(..)));
+        if !starts_with_heading || section.is_merged() {
+            out.push_str(".SH DESCRIPTION\n");
+        }
+
+        for part in &section.parts {
+            self.source = part.source.clone();
+            if section.is_merged() {
+                out.push_str(&format!(".PP\n\\fI{}\\fR\n", escape(&part.source)));
+            }
+            self.blocks(&mut out, &part.blocks);
+        }
+
+        if !self.notes.is_empty() {
+            out.push_str(".SH NOTES\n");
+            for (i, note) in self.notes.iter().enumerate() {
+                out.push_str(&format!(".IP \" {}.\" 4\n{}\n", i + 1, escape(note)));
+            }
+        }
+        out
+    }
+
+    fn blocks(&mut self, out: &mut String, blocks: &[Block]) {
+        for block in blocks {
+            match block {
+                Block::Header(spans, level) => {
+                    let macro_name = if *level <= 3 { "SH" } else { "SS" };
+                    let text = self.spans(spans).replace('"', "\\(dq");
+                    out.push_str(&format!(".{macro_name} \"{text}\"\n"));
+                }
+                Block::Paragraph(spans) => {
+                    out.push_str(".PP\n");
+                    push_lines(out, &self.spans(spans));
+                }
+                Block::Blockquote(blocks) => {
+                    out.push_str(".RS 4\n");
+                    self.blocks(out, blocks);
+                    out.push_str(".RE\n");
+                }
+                Block::CodeBlock(_, code) => {
+                    out.push_str(".PP\n.RS 4\n.nf\n");
+                    push_lines(out, &escape(code));
+                    out.push_str(".fi\n.RE\n");
+                }
+                Block::OrderedList(items, _) => self.items(out, items, true),
+                Block::UnorderedList(items) => self.items(out, items, false),
+                Block::Raw(text) => {
+                    out.push_str(".PP\n");
+                    push_lines(out, &escape(text));
+                }
+                Block::Hr => out.push_str(".PP\n\\l'20'\n"),
+            }
+        }
+    }
+
+    fn items(&mut self, out: &mut String, items: &[ListItem], ordered: bool) {
+        out.push_str(".RS 2\n");
+        for (i, item) in items.iter().enumerate() {
+            if ordered {
+                out.push_str(&format!(".IP \"{}.\" 4\n", i + 1));
+            } else {
+                out.push_str(".IP \\(bu 2\n");
+            }
+            match item {
+                ListItem::Simple(spans) => push_lines(out, &self.spans(spans)),
+                ListItem::Paragraph(blocks) => {
+                    let mut item = String::new();
+                    self.blocks(&mut item, blocks);
+                    // the first paragraph belongs to the .IP itself
+                    out.push_str(item.strip_prefix(".PP\n").unwrap_or(&item));
+                }
+            }
+        }
+        out.push_str(".RE\n");
+    }
+
+    fn spans(&mut self, spans: &[Span]) -> String {
+        let mut text = String::new();
+        for span in spans {
+            match span {
+                Span::Break => text.push_str(&format!("\n{BREAK}\n")),
+                Span::Text(t) => {
+                    if !t.starts_with("<!--") {
+                        text.push_str(&escape(t));
+                    }
+                }
+                Span::Code(c) => text.push_str(&format!("\\fB{}\\fR", escape(c))),
+                Span::Link(t, href, _) => {
+                    let n = self.note(href);
+                    text.push_str(&format!("{}\\&[{n}]", escape(t)));
+                }
+                Span::Image(alt, src, _) => {
+                    let n = self.note(src);
+                    text.push_str(&format!("[image: {}]\\&[{n}]", escape(alt)));
+                }
+                Span::Emphasis(inner) => {
+                    text.push_str(&format!("\\fI{}\\fR", self.spans(inner)));
+                }
+                Span::Strong(inner) => {
+                    text.push_str(&format!("\\fB{}\\fR", self.spans(inner)));
+                }
+            }
+        }
+        text
+    }
+
+    /// links to other sections point to their man page
+    fn note(&mut self, href: &str) -> usize {
+        let note = match self.anchors.resolve(&self.source, href) {
+            Link::Internal(anchor) => {
+                let id = anchor.split('/').next().unwrap_or_default();
+                format!("{id}({MAN_SECTION})")
+            }
+            _ => href.to_owned(),
+        };
+        match self.notes.iter().position(|n| *n == note) {
+            Some(i) => i + 1,
+            None => {
+                self.notes.push(note);
+                self.notes.len()
+            }
+        }
+    }
+}
+
+/// lines starting with a dot or a quote would be read as requests
+fn push_lines(out: &mut String, text: &str) {
+    for line in text.lines() {
+        if line == BREAK {
+            out.push_str(".br\n");
+            continue;
+        }
+        if line.starts_with(['.', '\'']) {
+            out.push_str("\\&");
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+    use crate::frontmatter::FrontMatter;
+    use crate::indexer::SectionPart;
+
+    #[test]
+    fn escapes_roff_requests_and_backslashes() {
+        let part = SectionPart {
+            title: "Setup".to_owned(),
+            source: "guide.md".to_owned(),
+            front_matter: FrontMatter::default(),
+            blocks: markdown::tokenize(
+                ".env files live in `C:\\sych` next to the\n'quoted' [docs](https://example.com).\n\n\
+                 ```\n.PP\nsych --root \\tmp\n```\n",
+            ),
+            git: None,
+            stale: None,
+        };
+        let mut docs_index = IndexMap::new();
+        docs_index.insert("Setup".to_owned(), Section { parts: vec![part] });
+        let anchors = Anchors::build(&docs_index);
+        let meta = Meta {
+            title: "Sych".to_owned(),
+            version: "1.0".to_owned(),
+            ..Meta::default()
+        };
+        let out = ManRenderer::new(&anchors).render(&meta, "Setup", &docs_index["Setup"]);
+        assert_eq!(
+            out,
+            ".TH \"SETUP\" \"7\" \"\" \"Sych 1.0\" \"Sych\"\n\
+             .SH NAME\n\
+             setup \\- Setup\n\
+             .SH DESCRIPTION\n\
+             .PP\n\
+             \\&.env files live in \\fBC:\\esych\\fR next to the 'quoted' docs\\&[1].\n\
+             .PP\n\
+             .RS 4\n\
+             .nf\n\
+             \\&.PP\n\
+             sych \\-\\-root \\etmp\n\
+             .fi\n\
+             .RE\n\
+             .SH NOTES\n\
+             .IP \" 1.\" 4\n\
+             https://example.com\n"
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use markdown::{Block, ListItem, Span};
+
+use crate::anchor::slugify;
+use crate::debt::glob_matches;
+use crate::git::history;
+use crate::harvest::relative_to;
+use crate::indexer::Section;
+use crate::project::covered_paths;
+use crate::OwnersConfig;
+
+const DEFAULT_TITLE: &str = "Who knows what";
+
+/// where a CODEOWNERS file is looked up, relative to the top of the
+/// repository
+pub(crate) const CODEOWNERS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// a line of a CODEOWNERS file, e.g. `/src/net/ @ana @net-team`
+#[derive(Debug)]
+pub(crate) struct Rule {
+    pattern: String,
+    owners: Vec<String>,
+}
+
+/// rules of a CODEOWNERS file, in the order they are written
+pub(crate) fn parse_codeowners(content: &str) -> Vec<Rule> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.split_once(" #").map_or(line, |(rule, _)| rule).trim();
+            if line.starts_with('#') {
+                return None;
+            }
+            let mut fields = line.split_whitespace();
+            Some(Rule {
+                pattern: fields.next()?.to_owned(),
+                owners: fields.map(str::to_owned).collect(),
+            })
+        })
+        .collect()
+}
+
+/// owners of the last rule matching `path`, relative to the top of the
+/// repository, as the last match wins
+fn owners_of<'a>(rules: &'a [Rule], path: &str) -> &'a [String] {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule_matches(&rule.pattern, path))
+        .map_or(&[], |rule| &rule.owners)
+}
+
+/// patterns work like the ones of .gitignore: they are anchored to the
+/// top when they hold a `/` before their end, and a directory matches
+/// everything inside of it
+fn rule_matches(pattern: &str, path: &str) -> bool {
+    let directory = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let glob = match trimmed.strip_prefix('/') {
+        Some(anchored) => anchored.to_owned(),
+        None if trimmed.contains('/') => trimmed.to_owned(),
+        None => format!("**/{trimmed}"),
+    };
+    (!directory && glob_matches(&glob, path)) || glob_matches(&format!("{glob}/**"), path)
+}
+
+/// who knows about a section
+#[derive(Debug)]
+pub(crate) struct Topic {
+    title: String,
+    /// link to the section, `None` for files outside of the root
+    href: Option<String>,
+    /// authors of the notes, from the front matter or git
+    notes: Vec<String>,
+    /// contributors of the covered code
+    code: Vec<String>,
+    owners: Vec<String>,
+    paths: Vec<CoveredPath>,
+}
+
+#[derive(Debug)]
+struct CoveredPath {
+    path: String,
+    contributors: Vec<String>,
+    owners: Vec<String>,
+}
+
+/// where the topics are looked up
+pub(crate) struct Lookup<'a> {
+    pub root: &'a Path,
+    /// directory of sych.toml, covered paths are relative to it
+    pub base: &'a Path,
+    /// top of the repository, CODEOWNERS patterns are relative to it
+    pub top: &'a Path,
+    pub rules: &'a [Rule],
+    /// read the contributors of the covered code with git
+    pub git: bool,
+    /// source of the generated sections, they are no topic of their own
+    pub generated: &'a str,
+}
+
+/// a topic for every section somebody is known to know about
+pub(crate) fn topics(sections: &IndexMap<String, Section>, lookup: &Lookup) -> Vec<Topic> {
+    let mut topics = vec![];
+    for (title, section) in sections {
+        let parts: Vec<_> = section
+            .parts
+            .iter()
+            .filter(|part| part.source != lookup.generated)
+            .collect();
+        let Some(first) = parts.first() else {
+            continue;
+        };
+        let mut topic = Topic {
+            title: title.clone(),
+            href: (!first.source.starts_with(".."))
+                .then(|| format!("/{}#{}", first.source, slugify(&first.title))),
+            notes: vec![],
+            code: vec![],
+            owners: vec![],
+            paths: vec![],
+        };
+
+        for part in parts {
+            let authors = match &part.front_matter.authors {
+                Some(authors) => authors.clone(),
+                None => part
+                    .git
+                    .as_ref()
+                    .map(|git| git.contributors.clone())
+                    .unwrap_or_default(),
+            };
+            extend_unique(&mut topic.notes, &authors);
+            let note = relative_to(lookup.top, &lookup.root.join(&part.source));
+            extend_unique(&mut topic.owners, owners_of(lookup.rules, &note));
+
+            for path in covered_paths(part) {
+                if topic.paths.iter().any(|covered| covered.path == path) {
+                    continue;
+                }
+                let file = lookup.base.join(&path);
+                let contributors = lookup
+                    .git
+                    .then(|| history(&file))
+                    .flatten()
+                    .map(|git| git.contributors)
+                    .unwrap_or_default();
+                let in_repository = relative_to(lookup.top, &file);
+                let owners = owners_of(lookup.rules, &in_repository).to_vec();
+                extend_unique(&mut topic.code, &contributors);
+                extend_unique(&mut topic.owners, &owners);
+                topic.paths.push(CoveredPath {
+                    path,
+                    contributors,
+                    owners,
+                });
+            }
+        }
+
+        if !(topic.notes.is_empty() && topic.code.is_empty() && topic.owners.is_empty()) {
+            topics.push(topic);
+        }
+    }
+    topics
+}
+
+fn extend_unique(people: &mut Vec<String>, more: &[String]) {
+    for person in more {
+        if !people.contains(person) {
+            people.push(person.clone());
+        }
+    }
+}
+
+/// the generated section, people with the topics they know about
+/// followed by the topics with the people knowing about them
+pub(crate) fn owners_blocks(owners: &OwnersConfig, topics: &[Topic]) -> Vec<Block> {
+    let title = owners.title.as_deref().unwrap_or(DEFAULT_TITLE);
+    let mut blocks = vec![
+        Block::Header(vec![Span::Text(title.to_owned())], 2),
+        Block::Paragraph(vec![Span::Text(
+            "Who wrote the notes of every section, who committed to the code they cover and who owns it according to CODEOWNERS."
+                .to_owned(),
+        )]),
+    ];
+    if topics.is_empty() {
+        blocks.push(Block::Paragraph(vec![Span::Text(
+            "Nobody is known to know about anything yet.".to_owned(),
+        )]));
+        return blocks;
+    }
+
+    // person -> topic and the roles the person has in it
+    let mut people: BTreeMap<&str, Vec<(&Topic, Vec<&str>)>> = BTreeMap::new();
+    for topic in topics {
+        let roles = [
+            ("notes", &topic.notes),
+            ("code", &topic.code),
+            ("owner", &topic.owners),
+        ];
+        for (role, persons) in roles {
+            for person in persons {
+                let entries = people.entry(person.as_str()).or_default();
+                match entries
+                    .iter_mut()
+                    .find(|(seen, _)| std::ptr::eq(*seen, topic))
+                {
+                    Some((_, roles)) => roles.push(role),
+                    None => entries.push((topic, vec![role])),
+                }
+            }
+        }
+    }
+
+    blocks.push(Block::Header(vec![Span::Text("People".to_owned())], 3));
+    let items = people
+        .into_iter()
+        .map(|(person, entries)| {
+            let mut spans = vec![
+                Span::Strong(vec![Span::Text(person.to_owned())]),
+                Span::Text(": ".to_owned()),
+            ];
+            for (i, (topic, roles)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::Text(", ".to_owned()));
+                }
+                spans.push(topic_span(topic));
+                spans.push(Span::Text(format!(" ({})", roles.join(", "))));
+            }
+            ListItem::Simple(spans)
+        })
+        .collect();
+    blocks.push(Block::UnorderedList(items));
+
+    blocks.push(Block::Header(vec![Span::Text("Topics".to_owned())], 3));
+    let items = topics
+        .iter()
+        .map(|topic| {
+            let mut spans = vec![topic_span(topic)];
+            spans.push(Span::Text(format!(
+                ": {}",
+                roles_text(&topic.notes, &topic.code, &topic.owners)
+            )));
+            if topic.paths.is_empty() {
+                return ListItem::Simple(spans);
+            }
+            let paths = topic
+                .paths
+                .iter()
+                .map(|covered| {
+                    ListItem::Simple(vec![
+                        Span::Code(covered.path.clone()),
+                        Span::Text(format!(
+                            ": {}",
+                            roles_text(&[], &covered.contributors, &covered.owners)
+                        )),
+                    ])
+                })
+                .collect();
+            ListItem::Paragraph(vec![Block::Paragraph(spans), Block::UnorderedList(paths)])
+        })
+        .collect();
+    blocks.push(Block::UnorderedList(items));
+    blocks
+}
+
+fn topic_span(topic: &Topic) -> Span {
+    match &topic.href {
+        Some(href) => Span::Link(topic.title.clone(), href.clone(), None),
+        None => Span::Text(topic.title.clone()),
+    }
+}
+
+/// e.g. "notes by Ana; code by Bo, Ana; owned by @net"
+fn roles_text(notes: &[String], code: &[String], owners: &[String]) -> String {
+    let roles: Vec<String> = [("notes by", notes), ("code by", code), ("owned by", owners)]
+        .into_iter()
+        .filter(|(_, people)| !people.is_empty())
+        .map(|(role, people)| format!("{role} {}", people.join(", ")))
+        .collect();
+    if roles.is_empty() {
+        "nobody known".to_owned()
+    } else {
+        roles.join("; ")
+    }
+}
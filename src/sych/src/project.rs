@@ -0,0 +1,1143 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use markdown::{Block, ListItem};
+
+use crate::anchor::Anchors;
+use crate::changelog::{
+    atom, changelog_blocks, compare, now, Change, Changelog, Snapshot, SYCH_SNAPSHOT,
+};
+use crate::check::{check, payload_problem, CheckInput, Diagnostic};
+use crate::config::{load_config, ConfigDiagnostic, LoadedConfig};
+use crate::debt::{debt_blocks, scan};
+use crate::epub::{civil_date, escape, write_epub};
+use crate::error::SourceSpan;
+use crate::export::{combined_text, JsonIndex, TextFlavor, TextStats};
+use crate::frontmatter::{split_front_matter, FrontMatter};
+use crate::git::{
+    commit_of, commit_time, files_at, history, read_at, staleness, tags, toplevel, GitInfo,
+};
+use crate::harvest::{harvest, relative_to, rust_files, RustFile};
+use crate::include::{resolve_includes, snippet_source, BrokenSnippet};
+use crate::indexer::{create_index, merge_file_index, Collision, Section, SectionPart};
+use crate::man::{ManRenderer, MAN_SECTION};
+use crate::owners::{owners_blocks, parse_codeowners, topics, Lookup, Rule, CODEOWNERS};
+use crate::refs::{normalize, ref_files, sync_refs, RefsChange};
+use crate::terminal::TerminalRenderer;
+use crate::transform::{self, Transform};
+use crate::transpiler::{Doc, Layout, VersionLink};
+use crate::{ChangelogConfig, Error, MergeStrategy, Result, SychConfig};
+
+static HBS_FILE: &str = include_str!("../templates/sych.hbs");
+static SYCH_HBS_NAME: &str = "salt.hbs";
+static PAGE_HBS_FILE: &str = include_str!("../templates/page.hbs");
+static PAGE_HBS_NAME: &str = "page.hbs";
+static SYCH_CSS: &str = include_str!("../templates/sych.css");
+static SYCH_JS: &str = include_str!("../templates/sych.js");
+
+pub const SYCH_TOML: &str = "sych.toml";
+
+/// label of the working tree among the versions
+const DEFAULT_CURRENT: &str = "latest";
+
+/// sets up a [`Project`], from a sych.toml or from a config built in code
+#[derive(Default)]
+pub struct ProjectBuilder {
+    config_path: Option<PathBuf>,
+    config: Option<SychConfig>,
+    root: Option<PathBuf>,
+    transforms: Vec<Box<dyn Transform>>,
+}
+
+impl ProjectBuilder {
+    /// reads this file instead of the sych.toml of the working directory,
+    /// the user config, sych.local.toml and SYCH_* variables are layered
+    /// on top of it
+    pub fn config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// uses the config as it is, nothing is read from disk
+    pub fn config(mut self, config: SychConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// folder searched for markdown files, takes precedence over `[doc] root`
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// registers a pass, it runs where `[[transforms]]` lists its name
+    /// or after the listed ones when it is not listed, a pass with the
+    /// name of a built-in one replaces it
+    pub fn transform(mut self, transform: impl Transform + 'static) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    pub fn build(self) -> Result<Project> {
+        let (loaded, config_path) = match self.config {
+            Some(config) => (LoadedConfig::from_config(config), None),
+            None => {
+                let path = self.config_path.unwrap_or_else(|| PathBuf::from(SYCH_TOML));
+                if !path.is_file() {
+                    return Err(Error::config(format!("{} does not exist", path.display())));
+                }
+                let loaded = load_config(&path)?;
+                (loaded, Some(path))
+            }
+        };
+
+        // `[doc] root` is relative to sych.toml, not to the working directory
+        let base = config_path
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        let root = self
+            .root
+            .or_else(|| {
+                let doc = loaded.sych_cfg.doc.as_ref()?;
+                doc.root.as_ref().map(|root| base.join(root))
+            })
+            .unwrap_or_else(|| {
+                if base.as_os_str().is_empty() {
+                    PathBuf::from(".")
+                } else {
+                    base.to_path_buf()
+                }
+            });
+
+        transform::check(&loaded.sych_cfg, config_path.as_deref(), &self.transforms)?;
+
+        Ok(Project {
+            loaded,
+            config_path,
+            root,
+            transforms: self.transforms,
+        })
+    }
+}
+
+/// a sych project, its configuration and where its markdown files are
+pub struct Project {
+    loaded: LoadedConfig,
+    /// none when the config was built in code
+    config_path: Option<PathBuf>,
+    root: PathBuf,
+    transforms: Vec<Box<dyn Transform>>,
+}
+
+/// markdown files found for a project
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Sources {
+    /// files which are indexed, in order
+    pub files: Vec<String>,
+    /// files matching `[doc] exclude`
+    pub excluded: Vec<String>,
+}
+
+impl Project {
+    pub fn builder() -> ProjectBuilder {
+        ProjectBuilder::default()
+    }
+
+    pub fn config(&self) -> &SychConfig {
+        &self.loaded.sych_cfg
+    }
+
+    /// the configuration with every default filled in
+    pub fn effective_config(&self) -> SychConfig {
+        self.loaded.effective()
+    }
+
+    /// every effective value along with the layer it came from, as
+    /// printed by `sych config show --origin`
+    pub fn config_origins(&self) -> Result<String> {
+        self.loaded.show_origins()
+    }
+
+    /// unknown keys found in the configuration
+    pub fn warnings(&self) -> &[ConfigDiagnostic] {
+        &self.loaded.warnings
+    }
+
+    pub fn config_path(&self) -> Option<&Path> {
+        self.config_path.as_deref()
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// the markdown files listed by `refs`, or every markdown file found
+    /// inside the root when there is no such list
+    pub fn sources(&self) -> Result<Sources> {
+        let files = match self.config().refs.as_ref() {
+            Some(refs) => {
+                ref_files(&self.root, refs).map_err(|missing| self.missing_refs(missing))?
+            }
+            None => discover(&self.root),
+        };
+        Ok(self.exclude(files))
+    }
+
+    /// an error for every ref which does not exist, pointing at where it
+    /// is listed in sych.toml
+    fn missing_refs(&self, missing: Vec<String>) -> Error {
+        let mut errors: Vec<Error> = missing
+            .into_iter()
+            .map(|reference| Error::Discovery {
+                message: format!(
+                    "refs lists {reference} which does not exist, do 'sych refs sync' to update it"
+                ),
+                span: self.config_span(&format!("\"{reference}\"")),
+            })
+            .collect();
+        if errors.len() == 1 {
+            errors.remove(0)
+        } else {
+            Error::Multiple(errors)
+        }
+    }
+
+    /// updates `refs` in sych.toml with the markdown files found inside
+    /// the root, only `refs` is touched so that comments and formatting
+    /// survive
+    pub fn sync_refs(&self) -> Result<RefsChange> {
+        let Some(config_path) = self.config_path.as_ref() else {
+            return Err(Error::config("refs can only be synced with a sych.toml"));
+        };
+        let sources = self.exclude(discover(&self.root));
+        let config = fs::read_to_string(config_path).map_err(Error::io(config_path))?;
+        let file = config_path.to_string_lossy();
+        let (updated, change) = sync_refs(&file, &config, &self.root, &sources.files)?;
+        if !change.added.is_empty() || !change.removed.is_empty() {
+            fs::write(config_path, updated).map_err(Error::io(config_path))?;
+        }
+        Ok(change)
+    }
+
+    /// reads every markdown file and splits them into sections, every
+    /// file is read even when one of them fails so that all errors are
+    /// reported at once, the transform pipeline runs over the result
+    pub fn index(&self) -> Result<Docs> {
+        let sources = self.sources()?;
+        let mut sections = IndexMap::new();
+        let mut collisions = vec![];
+        let mut errors = vec![];
+        // what every file was indexed from, snippet errors point into it
+        let mut markdown = HashMap::new();
+        for md_file_path in &sources.files {
+            let md_content = match fs::read_to_string(md_file_path) {
+                Ok(md_content) => md_content,
+                Err(e) => {
+                    errors.push(Error::io(md_file_path)(e));
+                    continue;
+                }
+            };
+            let source = relative_source(&self.root, md_file_path);
+            if let Err(e) = self.index_markdown(
+                md_file_path,
+                &source,
+                &md_content,
+                &mut sections,
+                &mut collisions,
+            ) {
+                errors.push(e);
+            }
+            markdown.insert(source, (md_file_path.clone(), md_content));
+        }
+        let rust_files = self.rust_files().unwrap_or_else(|e| {
+            errors.push(e);
+            vec![]
+        });
+        let notes = self
+            .config()
+            .harvest
+            .as_ref()
+            .and_then(|harvest| harvest.notes)
+            .unwrap_or(false);
+        for file in rust_files {
+            let content = match fs::read_to_string(&file.path) {
+                Ok(content) => content,
+                Err(e) => {
+                    errors.push(Error::io(&file.path)(e));
+                    continue;
+                }
+            };
+            let label = relative_to(&self.base(), &file.path);
+            let Some(blocks) = harvest(&file.module, &label, &content, notes) else {
+                continue;
+            };
+            let mut file_index = IndexMap::new();
+            create_index(blocks, &mut file_index);
+            merge_file_index(
+                self.config(),
+                &relative_to(&self.root, &file.path),
+                &FrontMatter::default(),
+                file_index,
+                &mut sections,
+                &mut collisions,
+            );
+        }
+        if let Some(debt) = self.config().debt.as_ref() {
+            let comments = scan(debt, &self.base(), &self.exclude_patterns());
+            let mut file_index = IndexMap::new();
+            create_index(debt_blocks(debt, &comments, &self.root), &mut file_index);
+            merge_file_index(
+                self.config(),
+                &self.generated_source(),
+                &FrontMatter::default(),
+                file_index,
+                &mut sections,
+                &mut collisions,
+            );
+        }
+        let base = self.base();
+        // broken snippets do not stop indexing, `sych check` reports
+        // them along with everything else and builds fail with them
+        let broken_snippets = resolve_includes(&mut sections, &base, &markdown, &|file| {
+            fs::read_to_string(base.join(file)).ok()
+        });
+        let rules = self.codeowners().unwrap_or_else(|e| {
+            errors.push(e);
+            vec![]
+        });
+        Error::collect(errors)?;
+        if self.config().git_metadata() {
+            self.attach_history(&mut sections);
+        }
+        if let Some(owners) = self.config().owners.as_ref() {
+            let top = toplevel(&base).unwrap_or_else(|| base.clone());
+            let generated = self.generated_source();
+            let topics = topics(
+                &sections,
+                &Lookup {
+                    root: &self.root,
+                    base: &base,
+                    top: &top,
+                    rules: &rules,
+                    git: self.config().git_metadata(),
+                    generated: &generated,
+                },
+            );
+            let mut file_index = IndexMap::new();
+            create_index(owners_blocks(owners, &topics), &mut file_index);
+            let front_matter = FrontMatter {
+                filter: Some(true),
+                ..FrontMatter::default()
+            };
+            merge_file_index(
+                self.config(),
+                &generated,
+                &front_matter,
+                file_index,
+                &mut sections,
+                &mut collisions,
+            );
+        }
+
+        let mut docs = Docs {
+            sych_cfg: self.config().clone(),
+            root: self.root.clone(),
+            base: self.base(),
+            sections,
+            collisions,
+            excluded: sources
+                .excluded
+                .iter()
+                .map(|f| relative_source(&self.root, f))
+                .collect(),
+            changelog: None,
+            versions: vec![],
+            broken_snippets,
+        };
+        transform::run(&mut docs, &self.transforms)?;
+        if let Some(changelog) = self.config().changelog.as_ref() {
+            self.add_changelog(&mut docs, changelog)?;
+        }
+        Ok(docs)
+    }
+
+    /// builds the working tree and every tag matching `[versions] tags`
+    /// into a directory per version inside `out_dir`, as a single html
+    /// page or as a site, with a switcher between them
+    ///
+    /// the tags are read from the local repository without checking
+    /// them out, the labels of the versions are returned with the
+    /// working tree first
+    pub fn write_versions(
+        &self,
+        out_dir: &Path,
+        site: bool,
+        minified: bool,
+    ) -> Result<Vec<String>> {
+        let config = self.config().versions.clone().unwrap_or_default();
+        let current = config.current.unwrap_or_else(|| DEFAULT_CURRENT.to_owned());
+        let pattern = config.tags.as_deref().unwrap_or("*");
+        let Some(tags) = tags(&self.root, pattern) else {
+            return Err(Error::Discovery {
+                message: format!(
+                    "versions are built from git tags, but {} is not inside a git repository",
+                    self.root.display()
+                ),
+                span: None,
+            });
+        };
+        let mut labels = vec![current.clone()];
+        labels.extend(tags.into_iter().filter(|tag| *tag != current));
+        let links = |active: &str| -> Vec<VersionLink> {
+            labels
+                .iter()
+                .map(|label| VersionLink {
+                    label: label.clone(),
+                    href: format!("../{}/index.html", version_dir(label)),
+                    active: label == active,
+                })
+                .collect()
+        };
+
+        for label in &labels {
+            let mut docs = if *label == current {
+                self.index()?
+            } else {
+                self.index_at(label)?
+            };
+            docs.versions = links(label);
+            let dir = out_dir.join(version_dir(label));
+            if site {
+                docs.write_site(&dir, minified)?;
+            } else {
+                fs::create_dir_all(&dir).map_err(Error::io(&dir))?;
+                docs.write_html(&dir.join("index.html"), minified)?;
+            }
+        }
+
+        // the top of the output leads to the working tree
+        let index = format!(
+            "<!DOCTYPE html>\n<meta charset=\"utf-8\">\n<meta http-equiv=\"refresh\" content=\"0; url={dir}/index.html\">\n<a href=\"{dir}/index.html\">{}</a>\n",
+            escape(&current),
+            dir = escape(&version_dir(&current)),
+        );
+        let index_path = out_dir.join("index.html");
+        fs::write(&index_path, index).map_err(Error::io(index_path))?;
+        Ok(labels)
+    }
+
+    /// compares the docs with `since`, or the snapshot of the last build,
+    /// and adds the "what changed" section, the docs are replaced by the
+    /// ones of `until` when it is set
+    fn add_changelog(&self, docs: &mut Docs, changelog: &ChangelogConfig) -> Result<()> {
+        let (new, updated, new_label) = match changelog.until.as_deref() {
+            Some(until) => {
+                let new = self.index_at(until)?;
+                let time = commit_time(&self.root, until).unwrap_or_else(now);
+                (
+                    Snapshot::of(&new.sections, time),
+                    time,
+                    revision_label(&self.root, until),
+                )
+            }
+            None => (
+                Snapshot::of(&docs.sections, now()),
+                now(),
+                "the working tree".to_owned(),
+            ),
+        };
+        let old = match changelog.since.as_deref() {
+            Some(since) => {
+                let old = self.index_at(since)?;
+                let time = commit_time(&self.root, since).unwrap_or_default();
+                Some((
+                    Snapshot::of(&old.sections, time),
+                    revision_label(&self.root, since),
+                ))
+            }
+            // a snapshot which cannot be read is as good as none, the
+            // next build writes a new one
+            None => fs::read_to_string(self.root.join(SYCH_SNAPSHOT))
+                .ok()
+                .and_then(|json| serde_json::from_str::<Snapshot>(&json).ok())
+                .map(|snapshot| {
+                    let (year, month, day) = civil_date(snapshot.time.div_euclid(86400));
+                    let label = format!("the build of {year:04}-{month:02}-{day:02}");
+                    (snapshot, label)
+                }),
+        };
+        let changes = old
+            .as_ref()
+            .map(|(old, _)| compare(old, &new))
+            .unwrap_or_default();
+
+        let blocks = changelog_blocks(
+            changelog,
+            &changes,
+            old.as_ref().map(|(_, label)| label.as_str()),
+            &new_label,
+            &docs.sections,
+        );
+        let mut file_index = IndexMap::new();
+        create_index(blocks, &mut file_index);
+        merge_file_index(
+            self.config(),
+            &self.generated_source(),
+            &FrontMatter::default(),
+            file_index,
+            &mut docs.sections,
+            &mut docs.collisions,
+        );
+        docs.changelog = Some(Changelog { changes, updated });
+        Ok(())
+    }
+
+    /// the markdown files as they were at a git revision, e.g. a tag,
+    /// read from the local repository without checking them out
+    ///
+    /// snippets are included from the same revision, harvested and
+    /// generated sections are left out
+    pub fn index_at(&self, revision: &str) -> Result<Docs> {
+        let Some(commit) = commit_of(&self.root, revision) else {
+            return Err(Error::Discovery {
+                message: format!(
+                    "revision {revision} does not exist in the git repository of {}",
+                    self.root.display()
+                ),
+                span: None,
+            });
+        };
+        let tree = files_at(&self.root, &commit).unwrap_or_default();
+        let files = match self.config().refs.as_ref() {
+            Some(refs) => {
+                let mut files: Vec<String> = vec![];
+                for reference in refs {
+                    let file = normalize(Path::new(reference))
+                        .to_string_lossy()
+                        .replace('\\', "/");
+                    if tree.contains(&file) && !files.contains(&file) {
+                        files.push(file);
+                    }
+                }
+                files
+            }
+            None => tree
+                .into_iter()
+                .filter(|file| file.ends_with(".md"))
+                .collect(),
+        };
+        let sources = self.exclude(files);
+
+        let mut sections = IndexMap::new();
+        let mut collisions = vec![];
+        let mut errors = vec![];
+        let mut markdown = HashMap::new();
+        for source in &sources.files {
+            let file = format!("{revision}:{source}");
+            let Some(md_content) = read_at(&self.root, &commit, source) else {
+                errors.push(Error::Discovery {
+                    message: format!("{file} cannot be read"),
+                    span: None,
+                });
+                continue;
+            };
+            if let Err(e) =
+                self.index_markdown(&file, source, &md_content, &mut sections, &mut collisions)
+            {
+                errors.push(e);
+            }
+            markdown.insert(source.clone(), (file, md_content));
+        }
+        let base = self.base();
+        // broken snippets do not stop indexing, `sych check` reports
+        // them along with everything else and builds fail with them
+        let broken_snippets = resolve_includes(&mut sections, &base, &markdown, &|file| {
+            read_at(&base, &commit, file)
+        });
+        Error::collect(errors)?;
+
+        let mut docs = Docs {
+            sych_cfg: self.config().clone(),
+            root: self.root.clone(),
+            base,
+            sections,
+            collisions,
+            excluded: sources.excluded,
+            changelog: None,
+            versions: vec![],
+            broken_snippets,
+        };
+        transform::run(&mut docs, &self.transforms)?;
+        Ok(docs)
+    }
+
+    /// splits a markdown file into sections and adds them to the index,
+    /// `file` names the file in errors and `source` is its path relative
+    /// to the root
+    fn index_markdown(
+        &self,
+        file: &str,
+        source: &str,
+        md_content: &str,
+        sections: &mut IndexMap<String, Section>,
+        collisions: &mut Vec<Collision>,
+    ) -> Result<()> {
+        // front matter is not markdown, it has to be removed before tokenizing
+        let (front_matter, md_content) = split_front_matter(file, md_content)?;
+        // convert markdown content to vec of markdown::Block
+        let tokens = markdown::tokenize(md_content);
+        // index all markdown files to convert into sections
+        // all Header2 tags will be converted to section headers on the left
+        // and all content between the Header2 will be the children of
+        // corresponding section
+        let mut file_index = IndexMap::new();
+        create_index(tokens, &mut file_index);
+
+        // sections with the same title across files are combined
+        // according to the configured merge strategy
+        merge_file_index(
+            self.config(),
+            source,
+            &front_matter,
+            file_index,
+            sections,
+            collisions,
+        );
+        Ok(())
+    }
+
+    /// git history of the file of every part, a file is only looked up
+    /// once however many sections it declares, parts whose covered code
+    /// changed too much since are marked as stale
+    fn attach_history(&self, sections: &mut IndexMap<String, Section>) {
+        let base = self.base();
+        let threshold = self.config().stale_threshold();
+        let mut histories: HashMap<String, Option<GitInfo>> = HashMap::new();
+        for part in sections.values_mut().flat_map(|section| &mut section.parts) {
+            part.git = histories
+                .entry(part.source.clone())
+                .or_insert_with(|| history(&self.root.join(&part.source)))
+                .clone();
+
+            let covered = covered_paths(part);
+            let Some(git) = part.git.as_ref().filter(|_| !covered.is_empty()) else {
+                continue;
+            };
+            part.stale = staleness(&base, &git.last_commit.hash, &covered)
+                .filter(|stale| stale.lines > threshold);
+        }
+    }
+
+    /// rust files of `[harvest] paths` minus the ones matching
+    /// `[doc] exclude`, every path which does not exist is an error
+    fn rust_files(&self) -> Result<Vec<RustFile>> {
+        let Some(harvest) = self.config().harvest.as_ref() else {
+            return Ok(vec![]);
+        };
+        let base = self.base();
+        let (found, missing): (Vec<&String>, Vec<&String>) = harvest
+            .paths
+            .iter()
+            .partition(|path| base.join(path).exists());
+        if !missing.is_empty() {
+            let errors = missing
+                .into_iter()
+                .map(|path| Error::Discovery {
+                    message: format!("[harvest] paths lists {path} which does not exist"),
+                    span: self.config_span(&format!("\"{path}\"")),
+                })
+                .collect();
+            return Error::collect(errors).map(|_| vec![]);
+        }
+
+        let exclude = self.exclude_patterns();
+        Ok(found
+            .into_iter()
+            .flat_map(|path| rust_files(&base.join(path)))
+            .filter(|file| {
+                let path = file.path.to_string_lossy();
+                !exclude.iter().any(|search_path| path.contains(search_path))
+            })
+            .collect())
+    }
+
+    /// the first occurrence of `needle` in sych.toml, for errors about
+    /// values of the config
+    fn config_span(&self, needle: &str) -> Option<SourceSpan> {
+        let file = self
+            .config_path
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| SYCH_TOML.to_owned());
+        let content = fs::read_to_string(&file).ok()?;
+        SourceSpan::find(&file, &content, needle)
+    }
+
+    /// source of the generated sections, sych.toml relative to the root
+    fn generated_source(&self) -> String {
+        match self.config_path.as_deref() {
+            Some(config_path) => relative_to(&self.root, config_path),
+            None => SYCH_TOML.to_owned(),
+        }
+    }
+
+    /// rules of the CODEOWNERS file of `[owners]`, a configured file
+    /// which does not exist is an error, a missing default one is not
+    fn codeowners(&self) -> Result<Vec<Rule>> {
+        let Some(owners) = self.config().owners.as_ref() else {
+            return Ok(vec![]);
+        };
+        let base = self.base();
+        let path = match &owners.codeowners {
+            Some(path) if base.join(path).is_file() => base.join(path),
+            Some(path) => {
+                return Err(Error::Discovery {
+                    message: format!("[owners] codeowners is {path} which does not exist"),
+                    span: self.config_span(&format!("\"{path}\"")),
+                });
+            }
+            None => {
+                let top = toplevel(&base).unwrap_or_else(|| base.clone());
+                match CODEOWNERS
+                    .iter()
+                    .map(|path| top.join(path))
+                    .find(|path| path.is_file())
+                {
+                    Some(path) => path,
+                    None => return Ok(vec![]),
+                }
+            }
+        };
+        let content = fs::read_to_string(&path).map_err(Error::io(&path))?;
+        Ok(parse_codeowners(&content))
+    }
+
+    /// directory files included by code fences and harvested files are
+    /// relative to, the one of sych.toml or the root when the config was
+    /// built in code
+    fn base(&self) -> PathBuf {
+        match self.config_path.as_deref().and_then(Path::parent) {
+            Some(dir) if dir.as_os_str().is_empty() => PathBuf::from("."),
+            Some(dir) => dir.to_path_buf(),
+            None => self.root.clone(),
+        }
+    }
+
+    fn exclude_patterns(&self) -> Vec<String> {
+        self.config()
+            .doc
+            .as_ref()
+            .and_then(|doc| doc.exclude.clone())
+            .unwrap_or_default()
+    }
+
+    /// splits the markdown files into the ones matching `[doc] exclude`
+    /// and the ones which are indexed
+    fn exclude(&self, markdown_files: Vec<String>) -> Sources {
+        let exclude = self.exclude_patterns();
+        let (excluded, files) = markdown_files.into_iter().partition(|md_file_path| {
+            exclude
+                .iter()
+                .any(|search_path| md_file_path.contains(search_path))
+        });
+        Sources { files, excluded }
+    }
+}
+
+/// the indexed docs of a project, ready to be checked or rendered
+pub struct Docs {
+    sych_cfg: SychConfig,
+    root: PathBuf,
+    /// directory of sych.toml, `covers` paths are relative to it
+    base: PathBuf,
+    sections: IndexMap<String, Section>,
+    collisions: Vec<Collision>,
+    /// excluded files, relative to the root
+    excluded: Vec<String>,
+    /// set when `[changelog]` is configured
+    changelog: Option<Changelog>,
+    /// the version switcher, when several versions are built
+    versions: Vec<VersionLink>,
+    /// code fences whose snippet cannot be included
+    broken_snippets: Vec<BrokenSnippet>,
+}
+
+impl Docs {
+    /// every section with its title, in navigation order
+    pub fn sections(&self) -> impl Iterator<Item = (&str, &Section)> {
+        self.sections
+            .iter()
+            .map(|(title, section)| (title.as_str(), section))
+    }
+
+    pub fn section(&self, title: &str) -> Option<&Section> {
+        self.sections.get(title)
+    }
+
+    /// every section with its title, for transforms to change in place
+    pub fn sections_mut(&mut self) -> impl Iterator<Item = (&str, &mut Section)> {
+        self.sections
+            .iter_mut()
+            .map(|(title, section)| (title.as_str(), section))
+    }
+
+    pub fn config(&self) -> &SychConfig {
+        &self.sych_cfg
+    }
+
+    /// what changed since `[changelog] since` or the last build, empty
+    /// without `[changelog]`
+    pub fn changes(&self) -> &[Change] {
+        self.changelog
+            .as_ref()
+            .map_or(&[], |changelog| changelog.changes.as_slice())
+    }
+
+    /// leaves the text of the sections for the next build to compare
+    /// with, see [`SYCH_SNAPSHOT`]
+    pub fn write_snapshot(&self, path: &Path) -> Result<()> {
+        let snapshot = Snapshot::of(&self.sections, now());
+        let json = serde_json::to_string(&snapshot).map_err(Error::render)?;
+        fs::write(path, json).map_err(Error::io(path))
+    }
+
+    /// an atom feed of the changes, `None` without `[changelog]`, entries
+    /// link to the single html page unless `site` is set
+    pub fn atom(&self, site: bool) -> Option<String> {
+        let changelog = self.changelog.as_ref()?;
+        let base_url = self
+            .sych_cfg
+            .site
+            .as_ref()
+            .and_then(|site| site.base_url.as_deref())
+            .map(|url| url.trim_end_matches('/').to_owned());
+        let anchors = Anchors::build(&self.sections);
+        let link = |title: &str| {
+            let base_url = base_url.as_ref()?;
+            let anchor = anchors.sections.get(title)?;
+            Some(if site {
+                format!("{base_url}/{anchor}/index.html")
+            } else {
+                format!("{base_url}/#{anchor}")
+            })
+        };
+        Some(atom(
+            &self.sych_cfg.meta,
+            &changelog.changes,
+            changelog.updated,
+            &link,
+        ))
+    }
+
+    /// folder the markdown files were found in
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// titles declared by more than one file
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// fails when a title is declared by more than one file and its
+    /// merge strategy is `error`, nothing else checks this before
+    /// rendering
+    pub fn check_collisions(&self) -> Result<()> {
+        let titles: Vec<String> = self
+            .collisions
+            .iter()
+            .filter(|c| c.strategy == MergeStrategy::Error)
+            .map(|c| c.title.clone())
+            .collect();
+        if titles.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::DuplicateSections(titles))
+        }
+    }
+
+    /// fails with every code fence whose snippet cannot be included,
+    /// the outputs need them while `sych check` only reports them
+    pub fn check_snippets(&self) -> Result<()> {
+        Error::collect(
+            self.broken_snippets
+                .iter()
+                .map(BrokenSnippet::error)
+                .collect(),
+        )
+    }
+
+    /// everything `sych check` reports, links are requested when a
+    /// cache directory for their results is given
+    pub fn check(&self, external_cache: Option<&Path>) -> Vec<Diagnostic> {
+        check(&CheckInput {
+            sych_cfg: &self.sych_cfg,
+            root: &self.root,
+            base: &self.base,
+            docs_index: &self.sections,
+            collisions: &self.collisions,
+            excluded: &self.excluded,
+            broken_snippets: &self.broken_snippets,
+            external_cache,
+        })
+    }
+
+    /// the single html file with every section as a tab
+    pub fn html(&self, minified: bool) -> Result<String> {
+        self.check_snippets()?;
+        self.check_extensions()?;
+        let doc = Doc::generate(
+            &self.sych_cfg,
+            &self.sections,
+            Layout::SinglePage,
+            self.versions.clone(),
+        );
+        let html = templates()?
+            .render(SYCH_HBS_NAME, &doc)
+            .map_err(Error::render)?;
+        if minified {
+            minify(&html)
+        } else {
+            Ok(html)
+        }
+    }
+
+    pub fn write_html(&self, path: &Path, minified: bool) -> Result<()> {
+        fs::write(path, self.html(minified)?).map_err(Error::io(path))
+    }
+
+    /// writes one page per section, an index page, the shared assets and
+    /// a sitemap when `[site] base_url` is set into `out_dir`, every link
+    /// between them is relative
+    pub fn write_site(&self, out_dir: &Path, minified: bool) -> Result<()> {
+        self.check_snippets()?;
+        self.check_extensions()?;
+        let reg = templates()?;
+        let doc = Doc::generate(
+            &self.sych_cfg,
+            &self.sections,
+            Layout::Site,
+            self.versions.clone(),
+        );
+
+        let assets_dir = out_dir.join("assets");
+        fs::create_dir_all(&assets_dir).map_err(Error::io(&assets_dir))?;
+        for (name, content) in [("sych.css", SYCH_CSS), ("sych.js", SYCH_JS)] {
+            let path = assets_dir.join(name);
+            fs::write(&path, content).map_err(Error::io(path))?;
+        }
+
+        for page in doc.pages() {
+            let mut html = reg.render(PAGE_HBS_NAME, &page).map_err(Error::render)?;
+            if minified {
+                html = minify(&html)?;
+            }
+            let page_path = out_dir.join(&page.path);
+            if let Some(parent) = page_path.parent() {
+                fs::create_dir_all(parent).map_err(Error::io(parent))?;
+            }
+            fs::write(&page_path, html).map_err(Error::io(page_path))?;
+        }
+
+        // sitemaps only take absolute urls, there is none without a
+        // base url
+        let base_url = self
+            .sych_cfg
+            .site
+            .as_ref()
+            .and_then(|site| site.base_url.as_ref())
+            .map(|url| format!("{}/", url.trim_end_matches('/')));
+        if let Some(base_url) = base_url {
+            let mut sitemap = String::from(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+"#,
+            );
+            for url in doc.page_urls() {
+                sitemap.push_str(&format!("  <url><loc>{base_url}{url}</loc></url>\n"));
+            }
+            sitemap.push_str("</urlset>\n");
+            let sitemap_path = out_dir.join("sitemap.xml");
+            fs::write(&sitemap_path, sitemap).map_err(Error::io(&sitemap_path))?;
+        }
+
+        if let Some(feed) = self.atom(true) {
+            let feed_path = out_dir.join("feed.xml");
+            fs::write(&feed_path, feed).map_err(Error::io(feed_path))?;
+        }
+        Ok(())
+    }
+
+    /// fails with every code fence whose payload would break the script
+    /// of its extension
+    fn check_extensions(&self) -> Result<()> {
+        let Some(extensions) = self.sych_cfg.extensions.as_ref() else {
+            return Ok(());
+        };
+        let mut errors = vec![];
+        for section in self.sections.values() {
+            for part in &section.parts {
+                for_each_fence(&part.blocks, &mut |meta, code| {
+                    let Some(extension) = extensions.get(meta) else {
+                        return;
+                    };
+                    let Some(problem) = payload_problem(extension, code) else {
+                        return;
+                    };
+                    let path = self.root.join(&part.source);
+                    let content = fs::read_to_string(&path).unwrap_or_default();
+                    errors.push(Error::Extension {
+                        name: meta.to_owned(),
+                        message: problem,
+                        span: SourceSpan::find(
+                            &path.to_string_lossy(),
+                            &content,
+                            &format!("```{meta}"),
+                        ),
+                    });
+                });
+            }
+        }
+        Error::collect(errors)
+    }
+
+    /// the whole index, including the rendered html of every section, as
+    /// described by schemas/sych-index.schema.json
+    pub fn json(&self) -> Result<String> {
+        self.check_snippets()?;
+        let doc = Doc::generate(&self.sych_cfg, &self.sections, Layout::SinglePage, vec![]);
+        let index = JsonIndex::new(&self.sych_cfg, &self.sections, &doc);
+        serde_json::to_string_pretty(&index).map_err(Error::render)
+    }
+
+    /// every section in one markdown or plain text document, along with
+    /// how big it is
+    pub fn text(&self, flavor: TextFlavor) -> (String, TextStats) {
+        combined_text(&self.sych_cfg, &self.sections, flavor)
+    }
+
+    /// an EPUB 3 book with one chapter per section
+    pub fn write_epub(&self, out: impl Write + Seek) -> Result<()> {
+        self.check_snippets()?;
+        write_epub(&self.sych_cfg, &self.root, &self.sections, out)
+    }
+
+    /// one roff man page per section, keyed by its file name, e.g.
+    /// `payments.7`
+    pub fn man_pages(&self) -> Vec<(String, String)> {
+        let anchors = Anchors::build(&self.sections);
+        self.sections
+            .iter()
+            .map(|(title, section)| {
+                let page = ManRenderer::new(&anchors).render(&self.sych_cfg.meta, title, section);
+                (format!("{}.{MAN_SECTION}", anchors.sections[title]), page)
+            })
+            .collect()
+    }
+
+    /// a section styled for the terminal, looked up by its title,
+    /// ignoring case, or by its anchor
+    pub fn terminal(&self, query: &str) -> Result<String> {
+        let anchors = Anchors::build(&self.sections);
+        let Some(title) = anchors.find_section(query) else {
+            return Err(Error::UnknownSection {
+                query: query.to_owned(),
+                sections: anchors.sections.keys().cloned().collect(),
+            });
+        };
+        Ok(TerminalRenderer::new(&anchors).render(title, &self.sections[title]))
+    }
+}
+
+/// the paths of `covers` followed by the files snippets are included
+/// from, relative to sych.toml
+pub(crate) fn covered_paths(part: &SectionPart) -> Vec<String> {
+    let mut covered = part.front_matter.covers.clone().unwrap_or_default();
+    for_each_fence(&part.blocks, &mut |meta, _| {
+        let Some(source) = snippet_source(meta) else {
+            return;
+        };
+        let file = source.rsplit_once(':').map_or(source, |(file, _)| file);
+        if !covered.iter().any(|path| path == file) {
+            covered.push(file.to_owned());
+        }
+    });
+    covered
+}
+
+fn for_each_fence(blocks: &[Block], f: &mut impl FnMut(&str, &str)) {
+    for block in blocks {
+        match block {
+            Block::CodeBlock(Some(meta), code) => f(meta, code),
+            Block::Blockquote(blocks) => for_each_fence(blocks, f),
+            Block::OrderedList(items, _) | Block::UnorderedList(items) => {
+                for item in items {
+                    if let ListItem::Paragraph(blocks) = item {
+                        for_each_fence(blocks, f);
+                    }
+                }
+            }
+            _ => continue,
+        }
+    }
+}
+
+/// finds every markdown file inside the root
+fn discover(root: &Path) -> Vec<String> {
+    rust_search::SearchBuilder::default()
+        .location(root)
+        .ext("md")
+        .build()
+        .collect()
+}
+
+/// path of a markdown file relative to the docs root, this is how
+/// files are referred to everywhere after discovery
+/// directory a version is written into, tags like `release/1.0` are
+/// kept in a single directory
+fn version_dir(label: &str) -> String {
+    label.replace(['/', '\\'], "-")
+}
+
+/// e.g. "v1.2 (3f2a9c1)"
+fn revision_label(dir: &Path, revision: &str) -> String {
+    match commit_of(dir, revision) {
+        Some(commit) if !commit.starts_with(revision) => {
+            format!("{revision} ({})", &commit[..7.min(commit.len())])
+        }
+        _ => revision.to_owned(),
+    }
+}
+
+fn relative_source(root: &Path, md_file_path: &str) -> String {
+    Path::new(md_file_path)
+        .strip_prefix(root)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| md_file_path.to_owned())
+}
+
+fn templates() -> Result<handlebars::Handlebars<'static>> {
+    let mut reg = handlebars::Handlebars::new();
+    reg.register_partial("sych_css", SYCH_CSS)
+        .map_err(Error::render)?;
+    reg.register_partial("sych_js", SYCH_JS)
+        .map_err(Error::render)?;
+    reg.register_template_string(SYCH_HBS_NAME, HBS_FILE)
+        .map_err(Error::render)?;
+    reg.register_template_string(PAGE_HBS_NAME, PAGE_HBS_FILE)
+        .map_err(Error::render)?;
+    Ok(reg)
+}
+
+fn minify(html: &str) -> Result<String> {
+    let mut cfg = minify_html::Cfg::spec_compliant();
+    cfg.minify_js = true;
+    cfg.minify_css = true;
+    let minified_bytes = minify_html::minify(html.as_bytes(), &cfg);
+    String::from_utf8(minified_bytes).map_err(Error::render)
+}
@@ -0,0 +1,223 @@
+use std::path::{Component, Path, PathBuf};
+
+use toml_edit::{Array, DocumentMut, Item, Value};
+
+use crate::error::SourceSpan;
+use crate::{Error, Result};
+
+/// markdown files listed by `refs`, in the order in which they are
+/// listed, paths are relative to the docs root. Fails with every ref
+/// which does not exist
+pub(crate) fn ref_files(root: &Path, refs: &[String]) -> Result<Vec<String>, Vec<String>> {
+    let mut files = vec![];
+    let mut missing = vec![];
+    for reference in refs {
+        // the root is kept as it is, so that the files can be made
+        // relative to it again
+        let path = root.join(normalize(Path::new(reference)));
+        if !path.is_file() {
+            missing.push(reference.clone());
+            continue;
+        }
+        let path = path.to_string_lossy().into_owned();
+        if !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    if missing.is_empty() {
+        Ok(files)
+    } else {
+        Err(missing)
+    }
+}
+
+/// what `sych refs sync` changed
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RefsChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// updates `refs` inside the config to the given markdown files, listed
+/// relative to the docs root. Files which are already listed keep their
+/// position and new files are appended, the rest of the config is left
+/// untouched, comments and formatting included, `file` is the name of
+/// the config in errors
+pub(crate) fn sync_refs(
+    file: &str,
+    config: &str,
+    root: &Path,
+    markdown_files: &[String],
+) -> Result<(String, RefsChange)> {
+    let mut document = config.parse::<DocumentMut>().map_err(|e| Error::Config {
+        message: e.message().trim_end().to_owned(),
+        span: e.span().map(|range| SourceSpan::new(file, config, range)),
+    })?;
+    let mut change = RefsChange::default();
+
+    let discovered: Vec<PathBuf> = markdown_files
+        .iter()
+        .map(|f| normalize(Path::new(f)))
+        .collect();
+    let is_discovered = |reference: &str| discovered.contains(&normalize(&root.join(reference)));
+
+    if !document.contains_key("refs") {
+        document["refs"] = Item::Value(Value::Array(Array::new()));
+    }
+    let Some(refs) = document["refs"].as_array_mut() else {
+        return Err(Error::Config {
+            message: "refs has to be a list of files".to_owned(),
+            span: SourceSpan::find(file, config, "refs"),
+        });
+    };
+
+    // new entries are written the same way as the existing ones
+    let first = refs.get(0);
+    let prefix = match first.and_then(|r| r.as_str()) {
+        Some(first) if first.starts_with("./") => "./",
+        _ => "",
+    };
+    let multiline = first
+        .and_then(|r| r.decor().prefix())
+        .and_then(|p| p.as_str())
+        .is_some_and(|p| p.contains('\n'));
+
+    // files outside of the root are never discovered, they are kept for
+    // as long as they exist
+    refs.retain(|reference| {
+        let Some(reference) = reference.as_str() else {
+            return false;
+        };
+        let path = normalize(&root.join(reference));
+        let keep = is_discovered(reference) || (is_outside(root, &path) && path.is_file());
+        if !keep {
+            change.removed.push(reference.to_owned());
+        }
+        keep
+    });
+
+    let listed: Vec<PathBuf> = refs
+        .iter()
+        .filter_map(|r| r.as_str())
+        .map(|r| normalize(&root.join(r)))
+        .collect();
+    let mut new_refs: Vec<String> = discovered
+        .iter()
+        .filter(|path| !listed.contains(path))
+        .map(|path| {
+            let relative = path.strip_prefix(normalize(root)).unwrap_or(path);
+            format!("{prefix}{}", relative.to_string_lossy())
+        })
+        .collect();
+    new_refs.sort();
+    for reference in new_refs {
+        if multiline {
+            refs.push_formatted(Value::from(reference.as_str()).decorated("\n    ", ""));
+        } else {
+            refs.push(reference.as_str());
+        }
+        change.added.push(reference);
+    }
+    if !multiline {
+        refs.fmt();
+    }
+
+    Ok((document.to_string(), change))
+}
+
+fn is_outside(root: &Path, path: &Path) -> bool {
+    path.starts_with("..") || !path.starts_with(normalize(root))
+}
+
+/// removes "." and resolves ".." without touching the file system, so
+/// that the same file is always written the same way
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => continue,
+            Component::ParentDir => {
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) {
+                    normalized.pop();
+                } else {
+                    normalized.push("..");
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| format!("/docs/{name}")).collect()
+    }
+
+    #[test]
+    fn syncs_an_inline_list_and_keeps_the_rest_of_the_config() {
+        let config = "# project docs\n\
+                      refs = [\"./b.md\", \"./gone.md\", \"./a.md\"] # in reading order\n\n\
+                      [meta]\n\
+                      title = \"t\" # shown in the header\n\
+                      version = \"1\"\n";
+        let (updated, change) = sync_refs(
+            "sych.toml",
+            config,
+            Path::new("/docs"),
+            &files(&["a.md", "b.md", "guide/c.md", "./d.md"]),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            "# project docs\n\
+             refs = [\"./b.md\", \"./a.md\", \"./d.md\", \"./guide/c.md\"] # in reading order\n\n\
+             [meta]\n\
+             title = \"t\" # shown in the header\n\
+             version = \"1\"\n"
+        );
+        assert_eq!(change.added, ["./d.md", "./guide/c.md"]);
+        assert_eq!(change.removed, ["./gone.md"]);
+    }
+
+    #[test]
+    fn syncs_a_list_over_several_lines() {
+        let config = "refs = [\n    \"a.md\",\n    # removed soon\n    \"gone.md\",\n]\n";
+        let (updated, change) = sync_refs(
+            "sych.toml",
+            config,
+            Path::new("/docs"),
+            &files(&["a.md", "b.md"]),
+        )
+        .unwrap();
+        assert_eq!(updated, "refs = [\n    \"a.md\",\n    \"b.md\",\n]\n");
+        assert_eq!(change.added, ["b.md"]);
+        assert_eq!(change.removed, ["gone.md"]);
+    }
+
+    #[test]
+    fn points_at_refs_which_are_not_a_list() {
+        let config = "refs = \"a.md\"\n\n[meta]\ntitle = \"t\"\n";
+        let Err(Error::Config { message, span }) =
+            sync_refs("sych.toml", config, Path::new("/docs"), &files(&["a.md"]))
+        else {
+            panic!("refs is a string");
+        };
+        assert_eq!(message, "refs has to be a list of files");
+        assert_eq!(span.map(|span| span.line), Some(1));
+
+        let Err(Error::Config { span, .. }) =
+            sync_refs("sych.toml", "refs = [\n", Path::new("/docs"), &[])
+        else {
+            panic!("the config is invalid toml");
+        };
+        assert_eq!(span.map(|span| span.file), Some("sych.toml".to_owned()));
+    }
+}
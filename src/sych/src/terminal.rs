@@ -0,0 +1,310 @@
+use std::io::IsTerminal;
+
+use markdown::{Block, ListItem, Span};
+
+use crate::anchor::{Anchors, Link};
+use crate::indexer::Section;
+
+const DEFAULT_WIDTH: usize = 80;
+const MAX_WIDTH: usize = 100;
+
+const BOLD: &str = "1";
+const ITALIC: &str = "3";
+const UNDERLINE: &str = "4";
+const DIM: &str = "2";
+const CYAN: &str = "36";
+const YELLOW: &str = "33";
+const GREEN: &str = "32";
+
+/// renders a section for reading in a terminal, links are numbered and
+/// listed as footnotes at the end
+pub(crate) struct TerminalRenderer<'a> {
+    anchors: &'a Anchors,
+    /// ANSI styling is left out when false, e.g. when piped to a file
+    color: bool,
+    width: usize,
+    source: String,
+    footnotes: Vec<String>,
+}
+
+impl<'a> TerminalRenderer<'a> {
+    pub fn new(anchors: &'a Anchors) -> Self {
+        let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        let width = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(DEFAULT_WIDTH)
+            .min(MAX_WIDTH);
+        TerminalRenderer {
+            anchors,
+            color,
+            width,
+            source: String::new(),
+            footnotes: vec![],
+        }
+    }
+
+    pub fn render(mut self, title: &str, section: &Section) -> String {
+        let mut out = format!("{}\n\n", self.style(&[BOLD, UNDERLINE], title));
+        for part in &section.parts {
+            self.source = part.source.clone();
+            if section.is_merged() {
+                let origin = format!("── {} ──", part.source);
+                out.push_str(&format!("{}\n\n", self.style(&[DIM], &origin)));
+            }
+            self.blocks(&mut out, &part.blocks, "");
+        }
+
+        if !self.footnotes.is_empty() {
+            out.push_str(&format!("{}\n", self.style(&[DIM], "links:")));
+            for (i, note) in self.footnotes.iter().enumerate() {
+                out.push_str(&format!("  [{}] {}\n", i + 1, note));
+            }
+        }
+        out
+    }
+
+    fn style(&self, codes: &[&str], text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{text}\x1b[0m", codes.join(";"))
+        } else {
+            text.to_owned()
+        }
+    }
+
+    fn blocks(&mut self, out: &mut String, blocks: &[Block], indent: &str) {
+        for block in blocks {
+            match block {
+                Block::Header(spans, level) => {
+                    let text = self.spans(spans);
+                    let heading = match level {
+                        ..=3 => self.style(&[BOLD, CYAN], &text),
+                        _ => self.style(&[BOLD], &text),
+                    };
+                    out.push_str(&format!("{indent}{heading}\n\n"));
+                }
+                Block::Paragraph(spans) => {
+                    let text = self.spans(spans);
+                    out.push_str(&self.wrap(&text, indent, indent));
+                    out.push('\n');
+                }
+                Block::Blockquote(blocks) => {
+                    // the quote is wrapped narrower to make room for the bar
+                    let width = self.width;
+                    self.width = width.saturating_sub(indent.chars().count() + 2);
+                    let mut quote = String::new();
+                    self.blocks(&mut quote, blocks, "");
+                    self.width = width;
+                    let bar = self.style(&[DIM], "│ ");
+                    for line in quote.trim_end().lines() {
+                        out.push_str(&format!("{indent}{bar}{line}\n"));
+                    }
+                    out.push('\n');
+                }
+                Block::CodeBlock(_, code) => {
+                    for line in code.lines() {
+                        let line = self.style(&[GREEN], line);
+                        out.push_str(&format!("{indent}    {line}\n"));
+                    }
+                    out.push('\n');
+                }
+                Block::OrderedList(items, _) => self.items(out, items, indent, true),
+                Block::UnorderedList(items) => self.items(out, items, indent, false),
+                Block::Raw(text) => out.push_str(&format!("{indent}{text}\n\n")),
+                Block::Hr => {
+                    let rule = "─".repeat(self.width.saturating_sub(indent.len()).min(40));
+                    out.push_str(&format!("{indent}{}\n\n", self.style(&[DIM], &rule)));
+                }
+            }
+        }
+    }
+
+    fn items(&mut self, out: &mut String, items: &[ListItem], indent: &str, ordered: bool) {
+        for (i, item) in items.iter().enumerate() {
+            let marker = if ordered {
+                format!("{:>2}. ", i + 1)
+            } else {
+                "  • ".to_owned()
+            };
+            let hanging = format!("{indent}{}", " ".repeat(marker.chars().count()));
+            match item {
+                ListItem::Simple(spans) => {
+                    let text = self.spans(spans);
+                    out.push_str(&self.wrap(&text, &format!("{indent}{marker}"), &hanging));
+                }
+                ListItem::Paragraph(blocks) => {
+                    let mut item = String::new();
+                    self.blocks(&mut item, blocks, &hanging);
+                    out.push_str(&format!("{indent}{marker}{}", item.trim_start()));
+                    // keep loose lists as tight as simple ones
+                    while out.ends_with("\n\n") {
+                        out.pop();
+                    }
+                }
+            }
+        }
+        out.push('\n');
+    }
+
+    fn spans(&mut self, spans: &[Span]) -> String {
+        let mut text = String::new();
+        for span in spans {
+            match span {
+                Span::Break => text.push('\n'),
+                Span::Text(t) => {
+                    if !t.starts_with("<!--") {
+                        text.push_str(t);
+                    }
+                }
+                Span::Code(c) => text.push_str(&self.style(&[YELLOW], c)),
+                Span::Link(t, href, _) => {
+                    let n = self.footnote(href);
+                    text.push_str(&self.style(&[UNDERLINE], t));
+                    text.push_str(&self.style(&[DIM], &format!("[{n}]")));
+                }
+                Span::Image(alt, src, _) => {
+                    let n = self.footnote(src);
+                    text.push_str(&self.style(&[DIM], &format!("[image: {alt}][{n}]")));
+                }
+                Span::Emphasis(inner) => {
+                    let inner = self.spans(inner);
+                    text.push_str(&self.style(&[ITALIC], &inner));
+                }
+                Span::Strong(inner) => {
+                    let inner = self.spans(inner);
+                    text.push_str(&self.style(&[BOLD], &inner));
+                }
+            }
+        }
+        text
+    }
+
+    /// number of the footnote of a link, links to other sections point
+    /// to the command which shows them
+    fn footnote(&mut self, href: &str) -> usize {
+        let note = match self.anchors.resolve(&self.source, href) {
+            Link::Internal(anchor) => match self.anchors.section_title(&anchor) {
+                Some(title) => format!("sych show \"{title}\""),
+                None => href.to_owned(),
+            },
+            _ => href.to_owned(),
+        };
+        match self.footnotes.iter().position(|n| *n == note) {
+            Some(i) => i + 1,
+            None => {
+                self.footnotes.push(note);
+                self.footnotes.len()
+            }
+        }
+    }
+
+    /// wraps the text at the terminal width, `first` is put in front of
+    /// the first line and `rest` in front of the others
+    fn wrap(&self, text: &str, first: &str, rest: &str) -> String {
+        let mut out = String::new();
+        let mut prefix = first;
+        for line in text.lines() {
+            let mut current = prefix.to_owned();
+            let mut current_width = visible_width(prefix);
+            let mut empty = true;
+            for word in line.split_whitespace() {
+                let width = visible_width(word);
+                if !empty && current_width + 1 + width > self.width {
+                    out.push_str(&current);
+                    out.push('\n');
+                    current = rest.to_owned();
+                    current_width = visible_width(rest);
+                    empty = true;
+                }
+                if !empty {
+                    current.push(' ');
+                    current_width += 1;
+                }
+                current.push_str(word);
+                current_width += width;
+                empty = false;
+            }
+            out.push_str(&current);
+            out.push('\n');
+            prefix = rest;
+        }
+        out
+    }
+}
+
+/// number of characters shown, without the ANSI escape sequences
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => continue,
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+    use crate::frontmatter::FrontMatter;
+    use crate::indexer::SectionPart;
+
+    fn render(markdown: &str) -> String {
+        let mut docs_index = IndexMap::new();
+        for (title, source, markdown) in [
+            ("Setup", "guide.md", markdown),
+            ("Payments", "payments.md", "Pay."),
+        ] {
+            let part = SectionPart {
+                title: title.to_owned(),
+                source: source.to_owned(),
+                front_matter: FrontMatter::default(),
+                blocks: markdown::tokenize(markdown),
+                git: None,
+                stale: None,
+            };
+            docs_index.insert(title.to_owned(), Section { parts: vec![part] });
+        }
+        let anchors = Anchors::build(&docs_index);
+        TerminalRenderer {
+            anchors: &anchors,
+            color: false,
+            width: 40,
+            source: String::new(),
+            footnotes: vec![],
+        }
+        .render("Setup", &docs_index["Setup"])
+    }
+
+    #[test]
+    fn renders_plain_text_without_color() {
+        let out = render(
+            "Install the **cli** with `cargo install sych` and read the [payments](payments.md) \
+             docs before the [website](https://example.com).\n\n\
+             ### Steps\n\n1. Clone\n2. Build\n\n- one\n- two\n\n> quoted\n\n```sh\nsych build\n```\n",
+        );
+        assert_eq!(
+            out,
+            "Setup\n\n\
+             Install the cli with cargo install sych\n\
+             and read the payments[1] docs before the\n\
+             website[2].\n\n\
+             Steps\n\n \
+             1. Clone\n \
+             2. Build\n\n  \
+             • one\n  \
+             • two\n\n\
+             │ quoted\n\n    \
+             sych build\n\n\
+             links:\n  \
+             [1] sych show \"Payments\"\n  \
+             [2] https://example.com\n"
+        );
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path};
+
+use markdown::{Block, ListItem, Span};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::anchor::{for_each_heading, spans_to_text, Slugger};
+use crate::error::SourceSpan;
+use crate::project::Docs;
+use crate::{Error, Result, SychConfig, TransformConfig};
+
+/// a pass over the indexed docs, run in order between indexing and
+/// rendering
+///
+/// passes are enabled by a `[[transforms]]` entry of sych.toml with
+/// their name, the other keys of the entry are their options:
+///
+/// ```toml
+/// [[transforms]]
+/// name = "redact"
+/// terms = ["internal.example.com"]
+/// ```
+pub trait Transform {
+    /// the name passes are enabled by in sych.toml
+    fn name(&self) -> &str;
+
+    /// changes the docs in place, `config` is the entry of sych.toml
+    /// which enabled the pass
+    fn apply(&self, docs: &mut Docs, config: &TransformConfig) -> Result<()>;
+}
+
+impl TransformConfig {
+    /// the options of the pass, every key of its entry except `name`
+    pub fn options<T: DeserializeOwned>(&self) -> Result<T> {
+        toml::Value::Table(self.options.clone())
+            .try_into()
+            .map_err(|e: toml::de::Error| {
+                Error::config(format!("invalid options, {}", e.message().trim_end()))
+            })
+    }
+}
+
+/// fails for every `[[transforms]]` entry naming neither a built-in nor
+/// a registered pass, pointing at its name in sych.toml
+pub(crate) fn check(
+    sych_cfg: &SychConfig,
+    config_path: Option<&Path>,
+    registered: &[Box<dyn Transform>],
+) -> Result<()> {
+    let content = config_path.and_then(|path| fs::read_to_string(path).ok());
+    let mut errors = vec![];
+    for config in sych_cfg.transforms.iter().flatten() {
+        if registered.iter().any(|t| t.name() == config.name)
+            || builtin_transform(&config.name).is_some()
+        {
+            continue;
+        }
+        let span = config_path
+            .zip(content.as_deref())
+            .and_then(|(path, content)| {
+                let entries = content.find("[[transforms]]")?;
+                let name = format!("\"{}\"", config.name);
+                let start = entries + content[entries..].find(&name)?;
+                Some(SourceSpan::new(
+                    &path.display().to_string(),
+                    content,
+                    start..start + name.len(),
+                ))
+            });
+        errors.push(Error::Config {
+            message: unknown_transform(&config.name, registered),
+            span,
+        });
+    }
+    Error::collect(errors)
+}
+
+fn unknown_transform(name: &str, registered: &[Box<dyn Transform>]) -> String {
+    let mut names: Vec<&str> = BUILTIN_TRANSFORMS.to_vec();
+    names.extend(registered.iter().map(|t| t.name()));
+    format!(
+        "unknown transform \"{name}\", the transforms are: {}",
+        names.join(", ")
+    )
+}
+
+/// runs the passes listed in sych.toml in order, followed by the passes
+/// registered in code which are not listed
+pub(crate) fn run(docs: &mut Docs, registered: &[Box<dyn Transform>]) -> Result<()> {
+    let configs = docs.config().transforms.clone().unwrap_or_default();
+    for config in &configs {
+        let builtin;
+        let transform = match registered.iter().find(|t| t.name() == config.name) {
+            Some(transform) => transform.as_ref(),
+            None => {
+                builtin = builtin_transform(&config.name)
+                    .ok_or_else(|| Error::config(unknown_transform(&config.name, registered)))?;
+                builtin.as_ref()
+            }
+        };
+        transform
+            .apply(docs, config)
+            .map_err(|e| failed(transform, e))?;
+    }
+
+    for transform in registered {
+        if !configs.iter().any(|config| config.name == transform.name()) {
+            let config = TransformConfig {
+                name: transform.name().to_owned(),
+                options: Default::default(),
+            };
+            transform
+                .apply(docs, &config)
+                .map_err(|e| failed(transform.as_ref(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// tells which pass failed, whatever the pass itself failed with
+fn failed(transform: &dyn Transform, error: Error) -> Error {
+    match error {
+        Error::Transform { .. } => error,
+        error => Error::Transform {
+            name: transform.name().to_owned(),
+            message: error.to_string(),
+        },
+    }
+}
+
+const BUILTIN_TRANSFORMS: &[&str] = &["rewrite_links", "redact", "glossary"];
+
+fn builtin_transform(name: &str) -> Option<Box<dyn Transform>> {
+    match name {
+        "rewrite_links" => Some(Box::new(RewriteLinks)),
+        "redact" => Some(Box::new(Redact)),
+        "glossary" => Some(Box::new(Glossary)),
+        _ => None,
+    }
+}
+
+/// replaces the start of link and image targets, e.g. after a wiki moved
+struct RewriteLinks;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RewriteLinksOptions {
+    from: String,
+    to: String,
+}
+
+impl Transform for RewriteLinks {
+    fn name(&self) -> &str {
+        "rewrite_links"
+    }
+
+    fn apply(&self, docs: &mut Docs, config: &TransformConfig) -> Result<()> {
+        let options: RewriteLinksOptions = config.options()?;
+        for (_, section) in docs.sections_mut() {
+            for part in &mut section.parts {
+                for_each_spans(&mut part.blocks, true, &mut |spans| {
+                    for_each_span(spans, &mut |span| {
+                        if let Span::Link(_, href, _) | Span::Image(_, href, _) = span {
+                            if let Some(rest) = href.strip_prefix(&options.from) {
+                                *href = format!("{}{rest}", options.to);
+                            }
+                        }
+                    })
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// hides terms which must not end up in published docs, e.g. internal
+/// host names
+struct Redact;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RedactOptions {
+    terms: Vec<String>,
+    #[serde(default = "RedactOptions::default_replacement")]
+    replacement: String,
+}
+
+impl RedactOptions {
+    fn default_replacement() -> String {
+        "[redacted]".to_owned()
+    }
+
+    fn redact(&self, text: &mut String) {
+        for term in self.terms.iter().filter(|term| !term.is_empty()) {
+            if text.contains(term.as_str()) {
+                *text = text.replace(term.as_str(), &self.replacement);
+            }
+        }
+    }
+}
+
+impl Transform for Redact {
+    fn name(&self) -> &str {
+        "redact"
+    }
+
+    fn apply(&self, docs: &mut Docs, config: &TransformConfig) -> Result<()> {
+        let options: RedactOptions = config.options()?;
+        for (_, section) in docs.sections_mut() {
+            for part in &mut section.parts {
+                for_each_block(&mut part.blocks, &mut |block| match block {
+                    Block::CodeBlock(_, code) => options.redact(code),
+                    Block::Raw(raw) => options.redact(raw),
+                    _ => (),
+                });
+                for_each_spans(&mut part.blocks, true, &mut |spans| {
+                    for_each_span(spans, &mut |span| match span {
+                        Span::Text(text) | Span::Code(text) => options.redact(text),
+                        Span::Link(text, href, _) | Span::Image(text, href, _) => {
+                            options.redact(text);
+                            options.redact(href);
+                        }
+                        _ => (),
+                    })
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// links the first mention of every term in a section to where the
+/// term is explained, the terms are the headings of the glossary section
+struct Glossary;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GlossaryOptions {
+    /// title of the section explaining the terms
+    section: String,
+}
+
+impl Transform for Glossary {
+    fn name(&self) -> &str {
+        "glossary"
+    }
+
+    fn apply(&self, docs: &mut Docs, config: &TransformConfig) -> Result<()> {
+        let options: GlossaryOptions = config.options()?;
+        let Some(glossary) = docs.section(&options.section) else {
+            return Err(Error::config(format!(
+                "the glossary section \"{}\" does not exist",
+                options.section
+            )));
+        };
+
+        // term -> (markdown file, slug of its heading)
+        let mut terms: Vec<(String, String, String)> = vec![];
+        for part in &glossary.parts {
+            // numbered like anchors of repeated headings of the file
+            let mut slugs = Slugger::default();
+            slugs.slug(&part.title);
+            for_each_heading(&part.blocks, &mut |heading| {
+                let term = spans_to_text(heading);
+                terms.push((term.clone(), part.source.clone(), slugs.slug(&term)));
+            });
+        }
+        // longer terms first so that "rate limit" wins over "rate"
+        terms.sort_by_key(|(term, _, _)| std::cmp::Reverse(term.chars().count()));
+
+        for (title, section) in docs.sections_mut() {
+            if title == options.section {
+                continue;
+            }
+            let mut linked: HashMap<&str, bool> = HashMap::new();
+            for part in &mut section.parts {
+                // links are relative to the file, which is unknown for
+                // files outside of the root
+                if part.source.starts_with("..") {
+                    continue;
+                }
+                for (term, source, slug) in &terms {
+                    if linked.contains_key(term.as_str()) {
+                        continue;
+                    }
+                    let href = format!("{}#{slug}", relative_href(&part.source, source));
+                    let mut done = false;
+                    for_each_spans(&mut part.blocks, false, &mut |spans| {
+                        if !done {
+                            done = link_first(spans, term, &href);
+                        }
+                    });
+                    if done {
+                        linked.insert(term, true);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// turns the first mention of `term` into a link, returns whether one
+/// was found
+fn link_first(spans: &mut Vec<Span>, term: &str, href: &str) -> bool {
+    for i in 0..spans.len() {
+        match &mut spans[i] {
+            Span::Text(text) => {
+                let Some(start) = find_word(text, term) else {
+                    continue;
+                };
+                let after = text.split_off(start + term.len());
+                let mention = text.split_off(start);
+                let link = Span::Link(mention, href.to_owned(), None);
+                spans.splice(i + 1..i + 1, [link, Span::Text(after)]);
+                return true;
+            }
+            Span::Emphasis(inner) | Span::Strong(inner) => {
+                if link_first(inner, term, href) {
+                    return true;
+                }
+            }
+            _ => continue,
+        }
+    }
+    false
+}
+
+/// position of `term` as a whole word inside `text`, ignoring case
+fn find_word(text: &str, term: &str) -> Option<usize> {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    // ascii lowercasing keeps the byte offsets of `text`
+    let lowercase = text.to_ascii_lowercase();
+    lowercase
+        .match_indices(&term.to_ascii_lowercase())
+        .map(|(start, _)| start)
+        .find(|&start| {
+            !is_word(text[..start].chars().next_back())
+                && !is_word(text[start + term.len()..].chars().next())
+        })
+}
+
+/// link from one markdown file to another, both relative to the root
+fn relative_href(from: &str, to: &str) -> String {
+    let depth = Path::new(from)
+        .parent()
+        .map(|dir| {
+            dir.components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .count()
+        })
+        .unwrap_or(0);
+    format!("{}{to}", "../".repeat(depth))
+}
+
+/// every block which holds no other blocks, nested ones included
+pub(crate) fn for_each_block(blocks: &mut [Block], f: &mut impl FnMut(&mut Block)) {
+    for block in blocks {
+        match block {
+            Block::Blockquote(inner) => for_each_block(inner, f),
+            Block::OrderedList(items, _) | Block::UnorderedList(items) => {
+                for item in items {
+                    if let ListItem::Paragraph(inner) = item {
+                        for_each_block(inner, f);
+                    }
+                }
+            }
+            _ => f(block),
+        }
+    }
+}
+
+/// every list of spans, the ones of headings only when `headings` is set
+fn for_each_spans(blocks: &mut [Block], headings: bool, f: &mut impl FnMut(&mut Vec<Span>)) {
+    for block in blocks {
+        match block {
+            Block::Header(spans, _) if headings => f(spans),
+            Block::Paragraph(spans) => f(spans),
+            Block::Blockquote(inner) => for_each_spans(inner, headings, f),
+            Block::OrderedList(items, _) | Block::UnorderedList(items) => {
+                for item in items {
+                    match item {
+                        ListItem::Simple(spans) => f(spans),
+                        ListItem::Paragraph(inner) => for_each_spans(inner, headings, f),
+                    }
+                }
+            }
+            _ => continue,
+        }
+    }
+}
+
+fn for_each_span(spans: &mut [Span], f: &mut impl FnMut(&mut Span)) {
+    for span in spans {
+        match span {
+            Span::Emphasis(inner) | Span::Strong(inner) => for_each_span(inner, f),
+            _ => f(span),
+        }
+    }
+}
@@ -0,0 +1,673 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use markdown::{Block, Span};
+use serde::Serialize;
+
+use crate::anchor::{spans_to_text, Anchors, Link, Slugger};
+use crate::epub::civil_date;
+use crate::frontmatter::FrontMatter;
+use crate::git::{GitInfo, Staleness};
+use crate::include::{highlight, snippet_source};
+use crate::indexer::Section;
+use crate::ExtensionMeta;
+
+use super::SychConfig;
+
+#[derive(Debug, Serialize)]
+pub struct Doc {
+    version: String,
+    project: String,
+    script_content: String,
+    // TODO: consider converting this to struct for clearer understanding
+    titles: Vec<(String, String, String)>,
+    pub(crate) contents: Vec<Content>,
+    about: String,
+    commands: Vec<(String, String)>,
+    authors: Vec<String>,
+    extensions: HashMap<String, ExtensionMeta>,
+    // FIXME: we need to make it a struct
+    /// extension_name, container, data
+    render_targets: Vec<(String, String, String)>,
+    /// entries of the version switcher, empty unless several versions
+    /// are built
+    pub(crate) versions: Vec<VersionLink>,
+}
+
+/// an entry of the version switcher, `href` is relative to the
+/// directory of the version
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct VersionLink {
+    pub label: String,
+    pub href: String,
+    pub active: bool,
+}
+
+/// how the generated docs are laid out, this decides what the links
+/// between sections look like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// one html file with every section as a tab
+    SinglePage,
+    /// one html page per section, at "<section>/index.html"
+    Site,
+}
+
+/// rendered body of a section (tab) of the documentation
+#[derive(Debug, Serialize)]
+pub(crate) struct Content {
+    pub title: String,
+    pub html: String,
+    pub id: String,
+    /// "true" for the section which is shown by default
+    active: String,
+    /// "on this page" entries of the section
+    toc: Vec<TocEntry>,
+    /// every heading of the section, regardless of the toc settings
+    #[serde(skip)]
+    pub headings: Vec<TocEntry>,
+    /// extension_name, container, data of this section only
+    render_targets: Vec<(String, String, String)>,
+    /// last commit of the section, `None` when git knows nothing of it
+    updated: Option<Updated>,
+}
+
+/// the "last updated by" line of a section
+#[derive(Debug, Serialize)]
+pub(crate) struct Updated {
+    /// e.g. "2024-05-01"
+    date: String,
+    /// e.g. "3 days ago"
+    ago: String,
+    author: String,
+    /// abbreviated hash of the last commit
+    commit: String,
+    subject: String,
+    contributors: Vec<String>,
+}
+
+impl Updated {
+    fn new(git: GitInfo, now: i64) -> Self {
+        let commit = git.last_commit;
+        let (year, month, day) = civil_date(commit.time.div_euclid(86400));
+        Updated {
+            date: format!("{year:04}-{month:02}-{day:02}"),
+            ago: ago((now - commit.time).max(0) / 86400),
+            author: commit.author,
+            commit: commit.hash.chars().take(7).collect(),
+            subject: commit.subject,
+            contributors: git.contributors,
+        }
+    }
+}
+
+/// how long ago something happened, in the largest unit that fits
+fn ago(days: i64) -> String {
+    match days {
+        0 => "today".to_owned(),
+        1 => "yesterday".to_owned(),
+        2..=59 => format!("{days} days ago"),
+        60..=729 => format!("{} months ago", days / 30),
+        _ => format!("{} years ago", days / 365),
+    }
+}
+
+/// deepest heading level listed in the table of contents of a file,
+/// 0 when it has none. Depths past `######` list every heading
+fn toc_level(front_matter: &FrontMatter, toc_depth: usize) -> usize {
+    let toc_depth = match front_matter.toc {
+        Some(false) => 0,
+        _ => front_matter.toc_depth.unwrap_or(toc_depth),
+    };
+    if toc_depth == 0 {
+        0
+    } else {
+        2 + toc_depth.min(4)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub(crate) struct TocEntry {
+    pub level: usize,
+    pub id: String,
+    pub text: String,
+}
+
+/// anchors handed out while rendering a single section
+struct SectionAnchors<'a> {
+    /// slug of the section itself, used as the prefix for all headings
+    id: String,
+    /// markdown file of the part being rendered, links are relative to it
+    source: String,
+    anchors: &'a Anchors,
+    layout: Layout,
+    /// every version of versioned output sits in a directory of its own
+    versioned: bool,
+    headings: Slugger,
+    /// deepest heading level which goes into the table of contents,
+    /// anything below 3 keeps the headings out of it
+    toc_level: usize,
+    toc: Vec<TocEntry>,
+    headings_seen: Vec<TocEntry>,
+}
+
+impl SectionAnchors<'_> {
+    /// anchor for a heading of this section, e.g. "payments/retries"
+    fn heading_id(&mut self, heading: &[Span]) -> String {
+        format!(
+            "{}/{}",
+            self.id,
+            self.headings.slug(&spans_to_text(heading))
+        )
+    }
+
+    /// href pointing to the anchor of a section or heading
+    fn href(&self, anchor: &str) -> String {
+        let section = anchor.split('/').next().unwrap_or_default();
+        match self.layout {
+            Layout::SinglePage => format!("#{anchor}"),
+            Layout::Site if section == self.id => format!("#{anchor}"),
+            Layout::Site if section == anchor => format!("../{section}/index.html"),
+            Layout::Site => format!("../{section}/index.html#{anchor}"),
+        }
+    }
+
+    /// href of a link to something outside of the docs, which is written
+    /// relative to the root, pages of a site and versions are one
+    /// directory deeper each
+    fn outside_href(&self, href: &str) -> String {
+        if href.starts_with(['/', '#']) {
+            return href.to_owned();
+        }
+        let mut prefix = String::new();
+        if self.layout == Layout::Site {
+            prefix.push_str("../");
+        }
+        if self.versioned {
+            prefix.push_str("../");
+        }
+        format!("{prefix}{href}")
+    }
+}
+
+/// an entry of the navigation of a multi-page site
+#[derive(Debug, Serialize)]
+struct NavLink {
+    title: String,
+    href: String,
+    active: bool,
+}
+
+/// a single html file of a multi-page site, either the index page or
+/// the page of a section
+#[derive(Debug, Serialize)]
+pub struct Page<'a> {
+    /// where the page is written, relative to the output directory
+    #[serde(skip)]
+    pub path: String,
+    doc: &'a Doc,
+    title: String,
+    content: Option<&'a Content>,
+    nav: Vec<NavLink>,
+    prev: Option<NavLink>,
+    next: Option<NavLink>,
+    /// relative path to the shared css and js files
+    assets: String,
+    /// relative path to the index page
+    home: String,
+    render_targets: &'a [(String, String, String)],
+    /// the version switcher, relative to the page
+    versions: Vec<VersionLink>,
+}
+
+fn spans_to_html(spans: &Vec<Span>, section: &SectionAnchors) -> String {
+    let mut html = String::new();
+    for span in spans {
+        match span {
+            markdown::Span::Break => {
+                html.push_str("<br />");
+            }
+            markdown::Span::Text(t) => {
+                if !t.starts_with("<!--") {
+                    html.push_str(t);
+                }
+            }
+            markdown::Span::Code(c) => {
+                html.push_str(&format!("<code>{c}</code>"));
+            }
+            markdown::Span::Link(text, link, _) => {
+                // links to other markdown files are rewritten to the
+                // anchors they end up with inside the docs
+                match section.anchors.resolve(&section.source, link) {
+                    Link::Internal(anchor) => {
+                        let href = section.href(&anchor);
+                        html.push_str(&format!(r#"<a href="{href}">{text}</a>"#));
+                    }
+                    Link::External => {
+                        html.push_str(&format!(
+                            r#"<a href="{link}" target="_blank" rel="noopener">{text}</a>"#
+                        ));
+                    }
+                    Link::Broken | Link::MissingAnchor | Link::Asset => {
+                        let href = section.outside_href(link);
+                        html.push_str(&format!(r#"<a href="{href}" target="_blank">{text}</a>"#));
+                    }
+                }
+            }
+            markdown::Span::Image(_, _, _) => continue,
+            markdown::Span::Emphasis(ispans) => {
+                let emph_html = spans_to_html(ispans, section);
+                html.push_str(&format!("<i>{}</i>", &emph_html));
+            }
+            markdown::Span::Strong(spans) => {
+                let strong_html = spans_to_html(spans, section);
+                html.push_str(&format!("<b>{}</b>", &strong_html));
+            }
+        }
+    }
+
+    html
+}
+
+// TODO: we need to pass sych config here to reduce param count
+#[allow(clippy::only_used_in_recursion)]
+fn blocks_to_html(
+    html: &mut String,
+    script_content: &mut String,
+    blocks: &[Block],
+    section: &mut SectionAnchors<'_>,
+    extensions: &Option<HashMap<String, ExtensionMeta>>,
+    // extension_name, container, data
+    render_targets: &mut Vec<(String, String, String)>,
+) {
+    for (i, block) in blocks.iter().enumerate() {
+        match block {
+            Block::Blockquote(bq) => {
+                html.push_str(r#"<div class="bq">"#);
+                blocks_to_html(
+                    html,
+                    script_content,
+                    bq,
+                    section,
+                    extensions,
+                    render_targets,
+                );
+                html.push_str("</div>");
+            }
+            Block::Header(h, hsize) => {
+                // h1 and h2 are taken by the document and the section
+                let level = (*hsize).clamp(3, 6);
+                let id = section.heading_id(h);
+                let entry = TocEntry {
+                    level,
+                    id: id.clone(),
+                    text: spans_to_text(h),
+                };
+                if level <= section.toc_level {
+                    section.toc.push(entry.clone());
+                }
+                section.headings_seen.push(entry);
+                html.push_str(&format!(r#"<h{level} id="{id}">"#));
+                html.push_str(&spans_to_html(h, section));
+                html.push_str(&format!(
+                    r##"<a class="s-anchor" href="#{id}" title="copy link">#</a></h{level}>"##
+                ));
+            }
+            Block::Paragraph(spans) => {
+                html.push_str(&spans_to_html(spans, section));
+                // peeking if next block is also a paragraph
+                if blocks.get(i + 1).is_some()
+                    && matches!(blocks.get(i + 1).unwrap(), Block::Paragraph(_))
+                {
+                    html.push_str("<br /><br />")
+                }
+            }
+            Block::CodeBlock(meta, cblock) => {
+                if let Some(m) = meta {
+                    if extensions.is_some() && extensions.as_ref().unwrap().contains_key(m) {
+                        let container = format!("{}-{}-{}", m, section.id, render_targets.len());
+                        render_targets.push((
+                            m.clone(),
+                            container.clone(),
+                            cblock.to_owned().replace('\n', ""),
+                        ));
+                        html.push_str(&format!(
+                            "<div style='margin: 1em' id='{}'>Loading extension {} ...</div>",
+                            container,
+                            m.clone()
+                        ));
+                    } else if let Some(source) = snippet_source(m) {
+                        // a snippet included from a file of the project
+                        let lang = m.split_whitespace().next().unwrap_or_default();
+                        html.push_str(&format!(
+                            r#"<figure class="s-snippet"><figcaption>{source}</figcaption>{}</figure>"#,
+                            highlight(lang, cblock)
+                        ));
+                    } else {
+                        // default case, it's just a pre block
+                        html.push_str(&format!("<pre>{cblock}</pre>"));
+                    }
+                } else {
+                    // default case, it's just a pre block
+                    html.push_str(&format!("<pre>{cblock}</pre>"));
+                }
+            }
+            Block::OrderedList(items, _) => {
+                html.push_str("<ol>");
+                // TODO: extract this for loop into a function
+                for item in items {
+                    match item {
+                        markdown::ListItem::Simple(t) => {
+                            html.push_str(&format!("<li>{}</li>", spans_to_html(t, section)));
+                        }
+                        markdown::ListItem::Paragraph(p) => {
+                            let mut list_para = String::from("<li>");
+                            blocks_to_html(
+                                &mut list_para,
+                                script_content,
+                                p,
+                                section,
+                                extensions,
+                                render_targets,
+                            );
+                            html.push_str(&list_para);
+                            html.push_str("</li>");
+                        }
+                    }
+                }
+                html.push_str("</ol>");
+            }
+            Block::UnorderedList(items) => {
+                html.push_str("<ul>");
+                for item in items {
+                    match item {
+                        markdown::ListItem::Simple(t) => {
+                            html.push_str(&format!("<li>{}</li>", spans_to_html(t, section)));
+                        }
+                        markdown::ListItem::Paragraph(p) => {
+                            let mut list_para = String::from("<li>");
+                            blocks_to_html(
+                                &mut list_para,
+                                script_content,
+                                p,
+                                section,
+                                extensions,
+                                render_targets,
+                            );
+                            html.push_str(&list_para);
+                            html.push_str("</li>");
+                        }
+                    }
+                }
+                html.push_str("</ul>");
+            }
+            _ => continue,
+        }
+    }
+}
+
+fn get_html(
+    blocks: &[Block],
+    script_content: &mut String,
+    section: &mut SectionAnchors<'_>,
+    extenstions: &Option<HashMap<String, ExtensionMeta>>,
+    render_targets: &mut Vec<(String, String, String)>,
+) -> String {
+    let mut html = String::new();
+    blocks_to_html(
+        &mut html,
+        script_content,
+        blocks,
+        section,
+        extenstions,
+        render_targets,
+    );
+    html
+}
+
+/// warns that the code a part covers changed since it was written
+fn stale_banner(stale: &Staleness) -> String {
+    let paths: Vec<String> = stale
+        .paths
+        .iter()
+        .map(|path| format!("<code>{path}</code>"))
+        .collect();
+    format!(
+        "<div class=\"s-stale\" role=\"note\"><b>Possibly outdated:</b> {} changed by {} lines in {} since this was last updated.</div>",
+        paths.join(", "),
+        stale.lines,
+        stale.commits_label()
+    )
+}
+
+impl Doc {
+    pub fn generate(
+        value: &SychConfig,
+        docs: &indexmap::IndexMap<String, Section>,
+        layout: Layout,
+        versions: Vec<VersionLink>,
+    ) -> Self {
+        let versioned = !versions.is_empty();
+        let mut doc = Doc {
+            version: value.meta.version.clone(),
+            project: value.meta.title.clone(),
+            script_content: String::new(),
+            contents: vec![],
+            titles: vec![],
+            about: value.meta.description.clone(),
+            commands: vec![],
+            authors: value.meta.authors.clone(),
+            extensions: if value.extensions.as_ref().is_none() {
+                HashMap::new()
+            } else {
+                value.extensions.as_ref().unwrap().clone()
+            },
+            render_targets: vec![],
+            versions,
+        };
+
+        let mut render_targets = vec![];
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+
+        // here we create map of all sections/titles
+        // (which is shown in the left side) of the documentation
+        let anchors = Anchors::build(docs);
+        for (i, (title, slug)) in anchors.sections.iter().enumerate() {
+            let data = (
+                title.to_owned(),
+                slug.to_owned(),
+                if i == 0 { "active".into() } else { "".into() },
+            );
+            doc.titles.push(data);
+        }
+
+        // here we push contents of each section
+        for (i, (title, content)) in docs.iter().enumerate() {
+            let mut section_targets = vec![];
+            let mut script_chunk = String::new();
+            let mut section = SectionAnchors {
+                id: anchors.sections[i].to_owned(),
+                source: String::new(),
+                anchors: &anchors,
+                layout,
+                versioned,
+                headings: Slugger::default(),
+                toc_level: 0,
+                toc: vec![],
+                headings_seen: vec![],
+            };
+
+            // every part is rendered on its own as the front matter of
+            // its file decides how its headings show up in the toc
+            let mut html = String::new();
+            for part in &content.parts {
+                section.toc_level = toc_level(&part.front_matter, value.toc_depth());
+                section.source = part.source.clone();
+
+                if content.is_merged() {
+                    html.push_str(&get_html(
+                        &[part.origin_heading()],
+                        &mut script_chunk,
+                        &mut section,
+                        &value.extensions,
+                        &mut section_targets,
+                    ));
+                }
+                if let Some(stale) = &part.stale {
+                    html.push_str(&stale_banner(stale));
+                }
+                if part.front_matter.filter == Some(true) {
+                    html.push_str(
+                        r#"<input class="s-filter" type="search" placeholder="Filter" aria-label="Filter">"#,
+                    );
+                }
+                html.push_str(&get_html(
+                    &part.blocks,
+                    &mut script_chunk,
+                    &mut section,
+                    &value.extensions,
+                    &mut section_targets,
+                ));
+            }
+            doc.script_content.push_str(&script_chunk);
+
+            // with corresponding:
+            // - html string
+            // - title of each section
+            // - and if we want to show that section active (default active: 0)
+            render_targets.extend(section_targets.iter().cloned());
+            doc.contents.push(Content {
+                title: title.to_owned(),
+                html,
+                id: section.id,
+                active: if i == 0 {
+                    "true".into()
+                } else {
+                    "false".into()
+                },
+                toc: section.toc,
+                headings: section.headings_seen,
+                render_targets: section_targets,
+                updated: content.git().map(|git| Updated::new(git, now)),
+            });
+        }
+
+        doc.render_targets = render_targets;
+
+        doc
+    }
+}
+
+impl Doc {
+    /// pages of a multi-page site, the index page comes first and is
+    /// followed by one page per section in navigation order
+    pub fn pages(&self) -> Vec<Page<'_>> {
+        let nav = |prefix: &str, active: Option<&str>| -> Vec<NavLink> {
+            self.contents
+                .iter()
+                .map(|c| NavLink {
+                    title: c.title.clone(),
+                    href: format!("{prefix}{}/index.html", c.id),
+                    active: Some(c.id.as_str()) == active,
+                })
+                .collect()
+        };
+        let versions = |prefix: &str| -> Vec<VersionLink> {
+            self.versions
+                .iter()
+                .map(|version| VersionLink {
+                    href: format!("{prefix}{}", version.href),
+                    ..version.clone()
+                })
+                .collect()
+        };
+
+        let mut pages = vec![Page {
+            path: "index.html".into(),
+            doc: self,
+            title: self.project.clone(),
+            content: None,
+            nav: nav("", None),
+            prev: None,
+            next: None,
+            assets: "assets".into(),
+            home: "index.html".into(),
+            render_targets: &[],
+            versions: versions(""),
+        }];
+
+        for (i, content) in self.contents.iter().enumerate() {
+            let mut links = nav("../", Some(&content.id));
+            let next = (i + 1 < links.len()).then(|| links.remove(i + 1));
+            let prev = (i > 0).then(|| links.remove(i - 1));
+            pages.push(Page {
+                path: format!("{}/index.html", content.id),
+                doc: self,
+                title: content.title.clone(),
+                content: Some(content),
+                nav: nav("../", Some(&content.id)),
+                prev,
+                next,
+                assets: "../assets".into(),
+                home: "../index.html".into(),
+                render_targets: &content.render_targets,
+                versions: versions("../"),
+            });
+        }
+        pages
+    }
+
+    /// url paths of every page, relative to the root of the site
+    pub fn page_urls(&self) -> Vec<String> {
+        let mut urls = vec![String::new()];
+        urls.extend(self.contents.iter().map(|c| format!("{}/", c.id)));
+        urls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_heading_ids_with_the_section() {
+        let anchors = Anchors::default();
+        let mut section = SectionAnchors {
+            id: "payments".to_owned(),
+            source: "payments.md".to_owned(),
+            anchors: &anchors,
+            layout: Layout::SinglePage,
+            versioned: false,
+            headings: Slugger::default(),
+            toc_level: 3,
+            toc: vec![],
+            headings_seen: vec![],
+        };
+        let retries = [Span::Text("Retries".to_owned())];
+        assert_eq!(section.heading_id(&retries), "payments/retries");
+        assert_eq!(section.heading_id(&retries), "payments/retries-1");
+        let styled = [
+            Span::Emphasis(vec![Span::Text("Why ".to_owned())]),
+            Span::Code("retry()".to_owned()),
+        ];
+        assert_eq!(section.heading_id(&styled), "payments/why-retry");
+    }
+
+    #[test]
+    fn clamps_the_toc_depth_to_the_heading_levels() {
+        let mut front_matter = FrontMatter::default();
+        assert_eq!(toc_level(&front_matter, 2), 4);
+        assert_eq!(toc_level(&front_matter, 0), 0);
+        assert_eq!(toc_level(&front_matter, 9), 6);
+        front_matter.toc_depth = Some(usize::MAX);
+        assert_eq!(toc_level(&front_matter, 2), 6);
+        front_matter.toc_depth = Some(0);
+        assert_eq!(toc_level(&front_matter, 2), 0);
+        front_matter.toc_depth = Some(3);
+        front_matter.toc = Some(false);
+        assert_eq!(toc_level(&front_matter, 2), 0);
+    }
+}
@@ -0,0 +1,86 @@
+// hash routing: #section or #section/heading opens the tab and scrolls to the heading,
+// pages of a multi-page site have no tabs so they only scroll
+function sychOpen(hash) {
+    const anchor = decodeURIComponent(hash.replace(/^#/, ""));
+    if (!anchor) return;
+    const section = anchor.split("/")[0];
+    const tab = document.getElementById(section + "-styled-tab");
+    if (tab) tab.click();
+    const target = document.getElementById(anchor);
+    if (target) target.scrollIntoView();
+}
+
+window.addEventListener("load", function () {
+    document.querySelectorAll("#default-styled-tab [role=tab]").forEach(function (tab) {
+        tab.addEventListener("click", function () {
+            const section = tab.id.replace(/-styled-tab$/, "");
+            const current = decodeURIComponent(location.hash.replace(/^#/, ""));
+            if (current.split("/")[0] !== section) {
+                history.replaceState(null, "", "#" + section);
+            }
+        });
+    });
+
+    document.querySelectorAll(".s-anchor").forEach(function (link) {
+        link.addEventListener("click", function (e) {
+            e.preventDefault();
+            const hash = link.getAttribute("href");
+            history.replaceState(null, "", hash);
+            if (navigator.clipboard) {
+                navigator.clipboard.writeText(location.href);
+            }
+            sychOpen(hash);
+        });
+    });
+
+    sychOpen(location.hash);
+});
+window.addEventListener("hashchange", function () {
+    sychOpen(location.hash);
+});
+
+// scroll-spy: highlights the toc entry of the heading currently in view
+function sychSpy() {
+    const container = document.getElementById("default-styled-tab-content");
+    const panel = container.querySelector("[role=tabpanel]:not(.hidden)");
+    if (!panel) return;
+    const top = container.getBoundingClientRect().top;
+    const links = panel.querySelectorAll(".s-toc a");
+    let current = null;
+    links.forEach(function (link) {
+        const heading = document.getElementById(link.getAttribute("href").slice(1));
+        if (heading && heading.getBoundingClientRect().top - top < 80) {
+            current = link;
+        }
+    });
+    links.forEach(function (link) {
+        link.classList.toggle("active", link === current);
+    });
+}
+
+window.addEventListener("load", function () {
+    document.getElementById("default-styled-tab-content").addEventListener("scroll", sychSpy);
+    sychSpy();
+});
+
+// filter boxes hide the list items of their section which do not
+// contain what is typed, ignoring case
+window.addEventListener("load", function () {
+    document.querySelectorAll(".s-filter").forEach(function (input) {
+        input.addEventListener("input", function () {
+            const query = input.value.trim().toLowerCase();
+            input.closest(".s-body").querySelectorAll(":scope > ul > li").forEach(function (item) {
+                item.hidden = query !== "" && !item.textContent.toLowerCase().includes(query);
+            });
+        });
+    });
+});
+
+// the version switcher opens the same docs built for another version
+window.addEventListener("load", function () {
+    document.querySelectorAll(".s-versions").forEach(function (select) {
+        select.addEventListener("change", function () {
+            location.href = select.value;
+        });
+    });
+});
@@ -0,0 +1,1325 @@
+use std::path::{Path, PathBuf};
+
+use sych::markdown::{Block, Span};
+use sych::{
+    ChangeKind, Doc, Docs, Error, ExtensionMeta, MergeStrategy, Project, SectionConfig, Severity,
+    SiteConfig, SychConfig, TextFlavor, Transform, TransformConfig, SYCH_SNAPSHOT,
+};
+
+/// a docs folder with the given markdown files
+fn docs_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sych-api-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, content) in files {
+        std::fs::write(dir.join(file), content).unwrap();
+    }
+    dir
+}
+
+/// runs git inside `dir`, committing as Ana unless told otherwise
+fn git(dir: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=Ana", "-c", "user.email=ana@example.com"])
+        .args(["-c", "commit.gpgsign=false"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn renders_a_config_built_in_code() {
+    let root = docs_dir(
+        "in-code",
+        &[
+            ("a.md", "## Payments\n\nHow we [refund](b.md#refunds).\n"),
+            ("b.md", "## Refunds\n\nWithin 30 days.\n"),
+        ],
+    );
+    let mut config = SychConfig::default();
+    config.meta.title = "Handbook".to_owned();
+
+    let project = Project::builder()
+        .config(config)
+        .root(&root)
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+
+    let mut titles: Vec<&str> = docs.sections().map(|(title, _)| title).collect();
+    titles.sort();
+    assert_eq!(titles, ["Payments", "Refunds"]);
+    assert!(docs.check(None).is_empty());
+
+    let html = docs.html(false).unwrap();
+    assert!(html.contains("Handbook"));
+    assert!(html.contains("Within 30 days."));
+}
+
+#[test]
+fn reads_sych_toml_relative_to_its_directory() {
+    let dir = docs_dir("toml", &[]);
+    std::fs::create_dir_all(dir.join("notes")).unwrap();
+    std::fs::write(dir.join("notes/a.md"), "## Setup\n\nRun it.\n").unwrap();
+    std::fs::write(
+        dir.join("sych.toml"),
+        "[meta]\ntitle = \"Notes\"\n\n[doc]\nroot = \"notes\"\n",
+    )
+    .unwrap();
+
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+    assert_eq!(project.root(), dir.join("notes"));
+    assert_eq!(project.sources().unwrap().files.len(), 1);
+    assert!(project.index().unwrap().section("Setup").is_some());
+}
+
+#[test]
+fn reports_errors_by_kind() {
+    let missing = Project::builder()
+        .config_path("/does/not/exist/sych.toml")
+        .build();
+    assert!(matches!(missing, Err(Error::Config { .. })));
+
+    let root = docs_dir(
+        "errors",
+        &[
+            ("a.md", "## Setup\n\nOne.\n"),
+            ("b.md", "## Setup\n\nTwo.\n"),
+            ("c.md", "---\ntoc: [\n---\n## Broken\n"),
+        ],
+    );
+    let mut doc = Doc::default();
+    doc.merge = Some(MergeStrategy::Error);
+    doc.exclude = Some(vec!["c.md".to_owned()]);
+    let mut config = SychConfig::default();
+    config.doc = Some(doc);
+    let project = Project::builder()
+        .config(config.clone())
+        .root(&root)
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+    assert!(matches!(
+        docs.check_collisions(),
+        Err(Error::DuplicateSections(titles)) if titles == ["Setup"]
+    ));
+
+    config.doc.as_mut().unwrap().exclude = None;
+    let project = Project::builder()
+        .config(config)
+        .root(&root)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        project.index(),
+        Err(Error::Parse { file, .. }) if file.ends_with("c.md")
+    ));
+}
+
+#[test]
+fn handles_duplicate_sections_by_their_strategy() {
+    let root = docs_dir(
+        "merge",
+        &[
+            (
+                "a.md",
+                "## Setup (b.md)\n\nTaken.\n\n## Setup\n\nOne.\n\n## Usage\n\nRun.\n\n## Deploy\n\nPush.\n",
+            ),
+            (
+                "b.md",
+                "## Setup\n\nTwo.\n\n## Usage\n\nStop.\n\n## Deploy\n\nPull.\n",
+            ),
+            ("c.md", "## Usage\n\nAgain.\n"),
+        ],
+    );
+    let section = |merge| {
+        let mut section = SectionConfig::default();
+        section.merge = Some(merge);
+        section
+    };
+    let mut doc = Doc::default();
+    doc.sections = Some(
+        [
+            ("Setup".to_owned(), section(MergeStrategy::Separate)),
+            ("Deploy".to_owned(), section(MergeStrategy::Error)),
+        ]
+        .into(),
+    );
+    let mut config = SychConfig::default();
+    config.doc = Some(doc);
+    config.refs = Some(vec![
+        "a.md".to_owned(),
+        "b.md".to_owned(),
+        "c.md".to_owned(),
+    ]);
+    let project = Project::builder()
+        .config(config)
+        .root(&root)
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+
+    let titles: Vec<&str> = docs.sections().map(|(title, _)| title).collect();
+    assert_eq!(
+        titles,
+        [
+            "Setup (b.md)",
+            "Setup (a.md)",
+            "Usage",
+            "Deploy",
+            "Setup (b.md, 2)"
+        ]
+    );
+    // the section literally titled "Setup (b.md)" is not replaced
+    assert_eq!(
+        docs.section("Setup (b.md)").unwrap().parts[0].source,
+        "a.md"
+    );
+    let usage = docs.section("Usage").unwrap();
+    assert!(usage.is_merged());
+    let sources: Vec<&str> = usage
+        .parts
+        .iter()
+        .map(|part| part.source.as_str())
+        .collect();
+    assert_eq!(sources, ["a.md", "b.md", "c.md"]);
+    assert_eq!(docs.section("Deploy").unwrap().parts.len(), 2);
+    assert_eq!(
+        docs.section("Setup (b.md, 2)").unwrap().parts[0].source,
+        "b.md"
+    );
+
+    let collisions: Vec<(&str, Vec<&str>, MergeStrategy)> = docs
+        .collisions()
+        .iter()
+        .map(|c| {
+            let sources = c.sources.iter().map(String::as_str).collect();
+            (c.title.as_str(), sources, c.strategy)
+        })
+        .collect();
+    assert_eq!(
+        collisions,
+        [
+            ("Setup", vec!["a.md", "b.md"], MergeStrategy::Separate),
+            ("Usage", vec!["a.md", "b.md", "c.md"], MergeStrategy::Merge),
+            ("Deploy", vec!["a.md", "b.md"], MergeStrategy::Error),
+        ]
+    );
+    assert!(matches!(
+        docs.check_collisions(),
+        Err(Error::DuplicateSections(titles)) if titles == ["Deploy"]
+    ));
+}
+
+#[test]
+fn reports_every_kind_of_problem_on_its_line() {
+    let a = [
+        "---",
+        "covers: [src/gone.rs]",
+        "---",
+        "## Setup guide",
+        "",
+        "Read [ok](b.md#usage-notes) first.",
+        "",
+        "## Setup",
+        "",
+        "Then [bad](b.md#usage) and [one](missing.md).",
+        "",
+        "Also [two](missing.md) and [draft](draft.md).",
+        "",
+        "![logo](logo.png)",
+        "",
+        "```chart",
+        "{\"a\": 1}",
+        "```",
+        "",
+        "```chart",
+        "not json",
+        "```",
+        "",
+        "```mermaid",
+        "graph",
+        "```",
+        "",
+        "## Empty",
+    ]
+    .join("\n");
+    let root = docs_dir(
+        "check",
+        &[
+            ("a.md", &a),
+            (
+                "b.md",
+                "## Setup\n\nAlso here.\n\n### Usage notes\n\nFine.\n",
+            ),
+            ("draft.md", "## Draft\n"),
+        ],
+    );
+    let mut chart = ExtensionMeta::default();
+    chart.url = "https://example.com/chart.js".to_owned();
+    chart.payload = Some("json".to_owned());
+    let mut doc = Doc::default();
+    doc.exclude = Some(vec!["draft.md".to_owned()]);
+    let mut config = SychConfig::default();
+    config.doc = Some(doc);
+    config.extensions = Some([("chart".to_owned(), chart)].into());
+    let project = Project::builder()
+        .config(config)
+        .root(&root)
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+
+    let diagnostics: Vec<(Severity, &str, String, Option<usize>)> = docs
+        .check(None)
+        .into_iter()
+        .map(|d| {
+            let file = Path::new(&d.file).strip_prefix(&root).unwrap();
+            (d.severity, d.code, file.display().to_string(), d.line)
+        })
+        .collect();
+    let expected = [
+        (Severity::Warning, "duplicate-title", "a.md", 8),
+        (Severity::Warning, "duplicate-title", "b.md", 1),
+        (Severity::Warning, "missing-cover", "a.md", 2),
+        // not the line of the longer link to a heading which exists
+        (Severity::Error, "missing-anchor", "a.md", 10),
+        (Severity::Error, "broken-link", "a.md", 10),
+        (Severity::Error, "broken-link", "a.md", 12),
+        (Severity::Error, "excluded-reference", "a.md", 12),
+        (Severity::Error, "missing-image", "a.md", 14),
+        // the second of the two fences of the extension
+        (Severity::Error, "extension-payload", "a.md", 20),
+        (Severity::Warning, "unknown-extension", "a.md", 24),
+        (Severity::Warning, "empty-section", "a.md", 28),
+    ]
+    .map(|(severity, code, file, line)| (severity, code, file.to_owned(), Some(line)));
+    assert_eq!(diagnostics, expected);
+}
+
+#[test]
+fn writes_a_page_per_section_with_relative_links() {
+    let root = docs_dir(
+        "site",
+        &[
+            (
+                "a.md",
+                "## Payments\n\nSee [limits](b.md#limits), [the code](src/lib.rs) and [top](#payments).\n",
+            ),
+            ("b.md", "## Refunds\n\n### Limits\n\nThirty days.\n"),
+        ],
+    );
+    let project = Project::builder()
+        .config(SychConfig::default())
+        .root(&root)
+        .build()
+        .unwrap();
+    let out = root.join("site");
+    project.index().unwrap().write_site(&out, false).unwrap();
+
+    let read = |path: &str| std::fs::read_to_string(out.join(path)).unwrap();
+    for path in ["assets/sych.css", "assets/sych.js", "refunds/index.html"] {
+        assert!(out.join(path).is_file(), "{path}");
+    }
+    let index = read("index.html");
+    assert!(index.contains(r#"href="payments/index.html""#));
+    assert!(index.contains(r#"href="assets/sych.css""#));
+
+    let payments = read("payments/index.html");
+    for href in [
+        "../refunds/index.html",
+        "../payments/index.html",
+        "../refunds/index.html#refunds/limits",
+        "../src/lib.rs",
+        "#payments",
+        "../assets/sych.css",
+        "../index.html",
+    ] {
+        assert!(payments.contains(&format!(r#"href="{href}""#)), "{href}");
+    }
+    // sitemaps only take absolute urls
+    assert!(!out.join("sitemap.xml").exists());
+
+    let mut site = SiteConfig::default();
+    site.base_url = Some("https://docs.example.com/sych".to_owned());
+    let mut config = SychConfig::default();
+    config.site = Some(site);
+    let project = Project::builder()
+        .config(config)
+        .root(&root)
+        .build()
+        .unwrap();
+    project.index().unwrap().write_site(&out, false).unwrap();
+    let mut locs: Vec<String> = read("sitemap.xml")
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("<url><loc>"))
+        .map(|line| line.trim_end_matches("</loc></url>").to_owned())
+        .collect();
+    locs.sort();
+    assert_eq!(
+        locs,
+        [
+            "https://docs.example.com/sych/",
+            "https://docs.example.com/sych/payments/",
+            "https://docs.example.com/sych/refunds/",
+        ]
+    );
+}
+
+/// the problems of `value` according to the parts of json schema the
+/// published schemas use, `path` is where `value` is in the document
+fn schema_problems(
+    root: &serde_json::Value,
+    schema: &serde_json::Value,
+    value: &serde_json::Value,
+    path: &str,
+) -> Vec<String> {
+    use serde_json::Value;
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.strip_prefix("#/$defs/").unwrap();
+        return schema_problems(root, &root["$defs"][name], value, path);
+    }
+    let mut problems = vec![];
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            // `type` is a property of blocks and spans
+            _ => vec![],
+        };
+        let matches = |t: &&str| match *t {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => true,
+        };
+        if !types.is_empty() && !types.iter().any(matches) {
+            problems.push(format!("{path}: {value} is not {types:?}"));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            problems.push(format!("{path}: {value} is not {expected}"));
+        }
+    }
+    if let Some(minimum) = schema["minimum"].as_i64() {
+        if value.as_i64().is_some_and(|v| v < minimum) {
+            problems.push(format!("{path}: {value} is below {minimum}"));
+        }
+    }
+    if let Some(maximum) = schema["maximum"].as_i64() {
+        if value.as_i64().is_some_and(|v| v > maximum) {
+            problems.push(format!("{path}: {value} is above {maximum}"));
+        }
+    }
+    for key in schema["required"].as_array().into_iter().flatten() {
+        let key = key.as_str().unwrap();
+        if value.get(key).is_none() {
+            problems.push(format!("{path}: {key} is missing"));
+        }
+    }
+    if let (Some(properties), Some(object)) = (schema["properties"].as_object(), value.as_object())
+    {
+        for (key, property) in properties {
+            if let Some(child) = object.get(key) {
+                problems.extend(schema_problems(
+                    root,
+                    property,
+                    child,
+                    &format!("{path}.{key}"),
+                ));
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            problems.extend(schema_problems(root, items, item, &format!("{path}[{i}]")));
+        }
+    }
+    if let Some(variants) = schema["oneOf"].as_array() {
+        let matching = variants
+            .iter()
+            .filter(|variant| schema_problems(root, variant, value, path).is_empty())
+            .count();
+        if matching != 1 {
+            problems.push(format!("{path}: {value} matches {matching} of oneOf"));
+        }
+    }
+    problems
+}
+
+#[test]
+fn exports_json_matching_the_published_schema() {
+    let root = docs_dir(
+        "json-schema",
+        &[
+            (
+                "a.md",
+                "---\ntoc: false\nowner: ana\n---\n## Payments\n\nWe *retry* **twice**, see [refunds](b.md) and `retry()`.  \nNext line.\n\n> Careful\n\n### Retries\n\n#### Backoff\n\n1. one\n2. two\n\n- a\n- b\n\n```rust\nfn retry() {}\n```\n\n![logo](logo.png \"Logo\")\n",
+            ),
+            ("b.md", "## Refunds\n\nWithin 30 days.\n"),
+        ],
+    );
+    git(&root, &["init", "-q"]);
+    git(&root, &["add", "."]);
+    git(&root, &["commit", "-qm", "Add notes"]);
+    let project = Project::builder()
+        .config(SychConfig::default())
+        .root(&root)
+        .build()
+        .unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&project.index().unwrap().json().unwrap()).unwrap();
+    let schema: serde_json::Value = serde_json::from_str(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../schemas/sych-index.schema.json"
+    )))
+    .unwrap();
+
+    assert_eq!(
+        schema_problems(&schema, &schema, &json, "$"),
+        Vec::<String>::new()
+    );
+    let sections = json["sections"].as_array().unwrap();
+    assert_eq!(sections.len(), 2);
+    let payments = sections.iter().find(|s| s["title"] == "Payments").unwrap();
+    assert_eq!(payments["headings"][0]["children"][0]["text"], "Backoff");
+    assert_eq!(payments["sources"][0]["front_matter"]["owner"], "ana");
+    assert_eq!(payments["git"]["last_commit"]["subject"], "Add notes");
+    // every kind of block made it into the export
+    let mut kinds: Vec<&str> = payments["sources"][0]["blocks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|block| block["type"].as_str().unwrap())
+        .collect();
+    kinds.sort();
+    kinds.dedup();
+    assert_eq!(
+        kinds,
+        ["blockquote", "code", "heading", "list", "paragraph"]
+    );
+
+    // the checker itself finds problems
+    let mut broken = json.clone();
+    broken["sections"][0]["headings"] = serde_json::json!([{ "level": 9, "id": "x" }]);
+    let problems = schema_problems(&schema, &schema, &broken, "$");
+    assert!(problems.contains(&"$.sections[0].headings[0]: text is missing".to_owned()));
+    assert!(problems.contains(&"$.sections[0].headings[0].level: 9 is above 6".to_owned()));
+}
+
+#[test]
+fn collects_every_error_with_its_span() {
+    let root = docs_dir(
+        "spans",
+        &[
+            ("a.md", "---\ntoc: 3\n---\n## A\n"),
+            ("b.md", "## B\n\n---\n\n## C\n"),
+            ("c.md", "---\ntitle: ok\ntoc_depth: deep\n---\n## D\n"),
+        ],
+    );
+    let project = Project::builder()
+        .config(SychConfig::default())
+        .root(&root)
+        .build()
+        .unwrap();
+    let Err(Error::Multiple(errors)) = project.index() else {
+        panic!("both broken files are reported");
+    };
+    assert_eq!(errors.len(), 2);
+
+    let spans: Vec<(usize, usize, &str)> = errors
+        .iter()
+        .map(|e| {
+            let span = e.span().unwrap();
+            (span.line, span.column, span.source_line.as_str())
+        })
+        .collect();
+    assert!(spans.contains(&(2, 6, "toc: 3")));
+    assert!(spans.contains(&(3, 12, "toc_depth: deep")));
+    assert_eq!(Error::Multiple(errors).exit_code(), 5);
+}
+
+#[test]
+fn reports_broken_extension_payloads_when_rendering() {
+    let root = docs_dir("extension", &[("a.md", "## A\n\n```req\n{nope\n```\n")]);
+    let config: SychConfig =
+        toml::from_str("[extensions.req]\nurl = \"req.js\"\npayload = \"json\"\n").unwrap();
+    let project = Project::builder()
+        .config(config)
+        .root(&root)
+        .build()
+        .unwrap();
+    let error = project.index().unwrap().html(false).unwrap_err();
+
+    assert_eq!(error.exit_code(), 7);
+    let report = error.report(false);
+    assert!(report.starts_with("error: payload of extension \"req\" cannot be parsed"));
+    assert!(report.contains("a.md:3:1\n"));
+    assert!(report.contains("3 | ```req\n"));
+}
+
+/// numbers the paragraphs of every section, a pass registered in code
+struct NumberParagraphs;
+
+impl Transform for NumberParagraphs {
+    fn name(&self) -> &str {
+        "number_paragraphs"
+    }
+
+    fn apply(&self, docs: &mut Docs, config: &TransformConfig) -> sych::Result<()> {
+        let prefix: String = config
+            .options::<toml::Table>()?
+            .get("prefix")
+            .and_then(|prefix| prefix.as_str())
+            .unwrap_or("")
+            .to_owned();
+        for (_, section) in docs.sections_mut() {
+            for part in &mut section.parts {
+                for (i, block) in part.blocks.iter_mut().enumerate() {
+                    if let Block::Paragraph(spans) = block {
+                        spans.insert(0, Span::Text(format!("{prefix}{} ", i + 1)));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn runs_transforms_in_order_before_rendering() {
+    let root = docs_dir(
+        "transforms",
+        &[
+            (
+                "glossary.md",
+                "## Glossary\n\n### SLA\n\nService level agreement.\n",
+            ),
+            ("a.md", "## Setup\n\nKeep the sla of corp.internal.\n"),
+        ],
+    );
+    let config: SychConfig = toml::from_str(
+        r#"
+        [[transforms]]
+        name = "number_paragraphs"
+        prefix = "§"
+
+        [[transforms]]
+        name = "glossary"
+        section = "Glossary"
+
+        [[transforms]]
+        name = "redact"
+        terms = ["corp.internal"]
+        "#,
+    )
+    .unwrap();
+    let project = Project::builder()
+        .config(config)
+        .root(&root)
+        .transform(NumberParagraphs)
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+
+    let text = docs.text(TextFlavor::Text).0;
+    assert!(text.contains("§1 Keep the sla (glossary.md#sla) of [redacted]."));
+    assert!(docs.check(None).is_empty());
+    assert!(docs.html(false).unwrap().contains("href=\"#glossary/sla\""));
+}
+
+#[test]
+fn reports_unknown_transforms_with_their_line() {
+    let dir = docs_dir("unknown-transform", &[("a.md", "## A\n")]);
+    std::fs::write(
+        dir.join("sych.toml"),
+        "[meta]\ntitle = \"A\"\n\n[[transforms]]\nname = \"redakt\"\n",
+    )
+    .unwrap();
+
+    let Err(error) = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+    else {
+        panic!("the transform does not exist");
+    };
+    assert_eq!(error.exit_code(), 3);
+    let span = error.span().unwrap();
+    assert_eq!((span.line, span.column), (5, 8));
+    assert!(error.to_string().contains("unknown transform \"redakt\""));
+}
+
+#[test]
+fn reports_failing_transforms_by_name() {
+    let dir = docs_dir("failing-transform", &[("a.md", "## A\n")]);
+    std::fs::write(
+        dir.join("sych.toml"),
+        "[meta]\ntitle = \"A\"\n\n[[transforms]]\nname = \"glossary\"\nsection = \"Terms\"\n",
+    )
+    .unwrap();
+
+    let error = match Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap()
+        .index()
+    {
+        Err(error) => error,
+        Ok(_) => panic!("the glossary section does not exist"),
+    };
+    assert!(matches!(&error, Error::Transform { name, .. } if name == "glossary"));
+    assert_eq!(error.exit_code(), 9);
+    assert_eq!(
+        error.to_string(),
+        "transform \"glossary\" failed, the glossary section \"Terms\" does not exist"
+    );
+}
+
+#[test]
+fn includes_snippets_of_the_project() {
+    let dir = docs_dir("include", &[]);
+    std::fs::create_dir_all(dir.join("docs")).unwrap();
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("src/cli.rs"),
+        "pub struct Cli;\n\nimpl Cli {\n    /// runs the command\n    pub fn execute(&self) {\n        // region: greet\n        println!(\"hi\");\n        // endregion: greet\n    }\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("sych.toml"), "[doc]\nroot = \"docs\"\n").unwrap();
+    std::fs::write(
+        dir.join("docs/a.md"),
+        "## Cli\n\n```rust file=src/cli.rs item=cli::Cli::execute\n```\n\n```rust file=src/cli.rs region=greet\n```\n\n```rust file=src/cli.rs lines=1\n```\n",
+    )
+    .unwrap();
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+
+    let docs = project.index().unwrap();
+    let snippets: Vec<(&str, &str)> = docs.section("Cli").unwrap().parts[0]
+        .blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::CodeBlock(Some(meta), code) => Some((meta.as_str(), code.as_str())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        snippets[0].1,
+        "/// runs the command\npub fn execute(&self) {\n    println!(\"hi\");\n}\n"
+    );
+    assert!(snippets[0].0.ends_with("source=src/cli.rs:4-9"));
+    assert_eq!(snippets[1].1, "println!(\"hi\");\n");
+    assert_eq!(snippets[2].1, "pub struct Cli;\n");
+    assert!(docs
+        .html(false)
+        .unwrap()
+        .contains("<figcaption>src/cli.rs:7</figcaption>"));
+
+    // the snippets are gone once the code changes, which `sych check`
+    // reports and every build fails with
+    std::fs::write(dir.join("src/cli.rs"), "pub struct Cli;\n").unwrap();
+    let docs = project.index().unwrap();
+    let missing: Vec<_> = docs
+        .check(None)
+        .into_iter()
+        .filter(|d| d.code == "missing-snippet")
+        .map(|d| d.line)
+        .collect();
+    assert_eq!(missing, [Some(3), Some(6)]);
+    let Err(Error::Multiple(errors)) = docs.html(false) else {
+        panic!("every missing snippet is reported");
+    };
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().all(|e| e.exit_code() == 4));
+    let span = errors[0].span().unwrap();
+    assert_eq!((span.line, span.column), (3, 25));
+}
+
+#[test]
+fn points_broken_snippets_of_a_revision_at_that_revision() {
+    let dir = docs_dir(
+        "include-revision",
+        &[
+            ("sych.toml", ""),
+            ("a.md", "## Cli\n\n```rust file=cli.rs region=greet\n```\n"),
+            ("cli.rs", "pub struct Cli;\n"),
+        ],
+    );
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "cli"]);
+    std::fs::write(
+        dir.join("a.md"),
+        "## Cli\n\nThe entry point.\n\n```rust file=cli.rs region=greet\n```\n",
+    )
+    .unwrap();
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+
+    let Err(Error::Discovery { span, .. }) = project.index_at("HEAD").unwrap().check_snippets()
+    else {
+        panic!("the region does not exist");
+    };
+    let span = span.unwrap();
+    assert_eq!((span.file.as_str(), span.line), ("HEAD:a.md", 3));
+
+    let Err(Error::Discovery { span, .. }) = project.index().unwrap().check_snippets() else {
+        panic!("the region does not exist");
+    };
+    assert_eq!(span.unwrap().line, 5);
+}
+
+#[test]
+fn reports_line_ranges_outside_of_the_file() {
+    let dir = docs_dir(
+        "include-lines",
+        &[
+            ("sych.toml", ""),
+            (
+                "a.md",
+                "## Cli\n\n```rust file=cli.rs lines=50-\n```\n\n```rust file=cli.rs lines=5-3\n```\n",
+            ),
+            ("cli.rs", "pub struct Cli;\nimpl Cli {}\n"),
+        ],
+    );
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+
+    let messages: Vec<String> = project
+        .index()
+        .unwrap()
+        .check(None)
+        .into_iter()
+        .map(|d| d.message)
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "lines 50- do not exist, the file has 2 lines, in cli.rs",
+            "invalid line range \"5-3\", expected e.g. 40-60, 40 or 40-",
+        ]
+    );
+}
+
+#[test]
+fn harvests_rust_doc_comments_as_sections() {
+    let dir = docs_dir("harvest", &[]);
+    std::fs::create_dir_all(dir.join("docs")).unwrap();
+    std::fs::create_dir_all(dir.join("payments/src")).unwrap();
+    std::fs::write(dir.join("docs/a.md"), "## Setup\n\nRun it.\n").unwrap();
+    std::fs::write(
+        dir.join("payments/src/refund.rs"),
+        "//! Refunds are paid back within 30 days.\n\n/// a refund of an order\n#[derive(Debug)]\npub struct Refund {\n    /// in cents\n    pub amount: u64,\n}\n\nimpl Refund {\n    /// pays the refund back\n    ///\n    /// # Errors\n    ///\n    /// ```no_run\n    /// # let refund = Refund { amount: 1 };\n    /// refund.pay()?;\n    /// ```\n    pub fn pay(&self) {\n        // SYCH: the bank api times out on fridays\n    }\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("sych.toml"),
+        "[doc]\nroot = \"docs\"\n\n[harvest]\npaths = [\"payments/src\"]\nnotes = true\n",
+    )
+    .unwrap();
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+
+    let titles: Vec<&str> = docs.sections().map(|(title, _)| title).collect();
+    assert_eq!(titles, ["Setup", "payments::refund"]);
+    let part = &docs.section("payments::refund").unwrap().parts[0];
+    assert_eq!(part.source, "../payments/src/refund.rs");
+    let headings: Vec<(String, usize)> = part
+        .blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Header(spans, level) => Some((format!("{spans:?}"), *level)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        headings,
+        [
+            ("[Code(\"Refund\")]".to_owned(), 3),
+            ("[Code(\"Refund::amount\")]".to_owned(), 3),
+            ("[Code(\"Refund::pay\")]".to_owned(), 3),
+            ("[Text(\"Errors\")]".to_owned(), 4),
+            ("[Text(\"Notes\")]".to_owned(), 3),
+        ]
+    );
+    assert!(part.blocks.iter().any(|block| matches!(
+        block,
+        Block::CodeBlock(Some(lang), code) if lang == "rust" && code == "refund.pay()?;"
+    )));
+
+    let text = docs.text(TextFlavor::Text).0;
+    assert!(text.contains("payments/src/refund.rs:19"));
+    assert!(text.contains("payments/src/refund.rs:20 the bank api times out on fridays"));
+    assert!(docs.check(None).is_empty());
+}
+
+#[test]
+fn lists_tagged_comments_in_a_debt_section() {
+    let dir = docs_dir("debt", &[]);
+    std::fs::create_dir_all(dir.join("docs")).unwrap();
+    std::fs::create_dir_all(dir.join("src/net")).unwrap();
+    std::fs::write(dir.join("docs/a.md"), "## Setup\n\nRun it.\n").unwrap();
+    std::fs::write(
+        dir.join("src/net/retry.rs"),
+        "// TODO(ana): retry on timeouts\nfn retry() {}\n/* FIXME flaky on ci */\nlet todo = \"TODO: not a comment\";\n// a TODO in the middle\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("src/deploy.sh"),
+        "# HACK: sleep until the db is up\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("sych.toml"),
+        "[doc]\nroot = \"docs\"\n\n[debt]\npaths = [\"src/**/*.rs\", \"src/*.sh\"]\ngroup_by = \"author\"\nblame = false\nurl = \"https://example.com/{path}#L{line}\"\n",
+    )
+    .unwrap();
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+
+    assert!(docs.section("Technical debt").is_some());
+    let text = docs.text(TextFlavor::Markdown).0;
+    assert!(text.contains("3 tagged comments: 1 TODO, 1 FIXME, 1 HACK."));
+    assert!(text.contains(
+        "### ana\n\n- `TODO` [src/net/retry.rs:1](https://example.com/src/net/retry.rs#L1) retry on timeouts"
+    ));
+    assert!(text.contains("### unknown"));
+    assert!(
+        text.contains("[src/net/retry.rs:3](https://example.com/src/net/retry.rs#L3) flaky on ci")
+    );
+    assert!(text.contains(
+        "[src/deploy.sh:1](https://example.com/src/deploy.sh#L1) sleep until the db is up"
+    ));
+    assert!(!text.contains("not a comment"));
+    assert!(!text.contains("in the middle"));
+}
+
+#[test]
+fn links_debt_comments_to_their_file_from_every_output() {
+    let dir = docs_dir("debt-links", &[]);
+    std::fs::create_dir_all(dir.join("docs")).unwrap();
+    std::fs::create_dir_all(dir.join("src/net")).unwrap();
+    std::fs::write(dir.join("docs/a.md"), "## Setup\n\nRun it.\n").unwrap();
+    std::fs::write(dir.join("src/net/retry.rs"), "// TODO: retry on timeouts\n").unwrap();
+    std::fs::write(
+        dir.join("sych.toml"),
+        "[doc]\nroot = \"docs\"\n\n[debt]\npaths = [\"src/**/*.rs\"]\ngroup_by = \"directory\"\nblame = false\n",
+    )
+    .unwrap();
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "debt"]);
+    git(&dir, &["tag", "v1"]);
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+
+    let text = docs.text(TextFlavor::Markdown).0;
+    assert!(text.contains("### src/net\n\n- `TODO` [src/net/retry.rs:1](../src/net/retry.rs)"));
+    let link = r#"href="../src/net/retry.rs""#;
+    assert!(docs.html(false).unwrap().contains(link));
+
+    // pages of a site and versions are a directory deeper each
+    let site = dir.join("site");
+    docs.write_site(&site, false).unwrap();
+    let page = std::fs::read_to_string(site.join("technical-debt/index.html")).unwrap();
+    assert!(page.contains(r#"href="../../src/net/retry.rs""#));
+
+    let versions = dir.join("versions");
+    project.write_versions(&versions, true, false).unwrap();
+    let page = std::fs::read_to_string(versions.join("latest/technical-debt/index.html")).unwrap();
+    assert!(page.contains(r#"href="../../../src/net/retry.rs""#));
+    project.write_versions(&versions, false, false).unwrap();
+    let page = std::fs::read_to_string(versions.join("latest/index.html")).unwrap();
+    assert!(page.contains(r#"href="../../src/net/retry.rs""#));
+}
+
+#[test]
+fn attaches_the_git_history_of_every_file() {
+    let dir = docs_dir(
+        "git",
+        &[
+            ("a.md", "## Setup\n\nRun it.\n"),
+            ("b.md", "## Setup\n\nThen check it.\n"),
+            ("sych.toml", "[meta]\ntitle = \"git\"\n"),
+        ],
+    );
+    let git = |args: &[&str]| git(&dir, args);
+    git(&["init", "-q"]);
+    git(&["add", "a.md", "sych.toml"]);
+    git(&[
+        "commit",
+        "-q",
+        "-m",
+        "add setup",
+        "--date=2020-01-01T00:00:00Z",
+    ]);
+    git(&["add", "b.md"]);
+    git(&[
+        "commit",
+        "-q",
+        "-m",
+        "check setup",
+        "--author=Bo <bo@example.com>",
+        "--date=2021-01-01T00:00:00Z",
+    ]);
+
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+    let section = docs.section("Setup").unwrap();
+    let parts: Vec<_> = section
+        .parts
+        .iter()
+        .map(|part| part.git.as_ref().unwrap().contributors.clone())
+        .collect();
+    assert_eq!(parts, [["Ana"], ["Bo"]]);
+
+    let git = section.git().unwrap();
+    assert_eq!(git.last_commit.author, "Bo");
+    assert_eq!(git.last_commit.subject, "check setup");
+    assert_eq!(git.last_commit.time, 1609459200);
+    assert_eq!(git.contributors, ["Ana", "Bo"]);
+
+    let html = docs.html(false).unwrap();
+    assert!(html.contains("data-updated=\"2021-01-01\" data-contributors=\"Ana, Bo\""));
+    assert!(html.contains("Last updated by Bo, <time datetime=\"2021-01-01\">"));
+    let json = docs.json().unwrap();
+    assert!(json.contains("\"subject\": \"check setup\""));
+
+    let mut config = project.config().clone();
+    config.doc.get_or_insert_with(Default::default).git = Some(false);
+    let docs = Project::builder()
+        .config(config)
+        .root(&dir)
+        .build()
+        .unwrap()
+        .index()
+        .unwrap();
+    assert!(docs.section("Setup").unwrap().git().is_none());
+}
+
+#[test]
+fn flags_notes_whose_covered_code_changed() {
+    let dir = docs_dir(
+        "stale",
+        &[
+            (
+                "a.md",
+                "---\ncovers: [src/retry.rs, src/gone.rs]\n---\n## Retries\n\nRetries three times.\n",
+            ),
+            ("b.md", "## Timeouts\n\n```rust file=src/timeout.rs\n```\n"),
+            ("sych.toml", "[check]\nstale_threshold = 2\n"),
+        ],
+    );
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/retry.rs"), "fn retry() {}\n").unwrap();
+    std::fs::write(dir.join("src/timeout.rs"), "fn timeout() {}\n").unwrap();
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "document retries"]);
+    std::fs::write(
+        dir.join("src/retry.rs"),
+        "fn retry() {\n    backoff();\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("src/timeout.rs"), "fn timeout(secs: u64) {}\n").unwrap();
+    git(&dir, &["commit", "-q", "-am", "back off between retries"]);
+
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+    let stale = docs.section("Retries").unwrap().parts[0]
+        .stale
+        .clone()
+        .unwrap();
+    assert_eq!(stale.paths, ["src/retry.rs"]);
+    assert_eq!((stale.commits, stale.lines), (1, 4));
+    // two lines of the included snippet changed, which is not above the threshold
+    assert!(docs.section("Timeouts").unwrap().parts[0].stale.is_none());
+
+    let html = docs.html(false).unwrap();
+    assert!(html.contains(
+        "<b>Possibly outdated:</b> <code>src/retry.rs</code> changed by 4 lines in 1 commit"
+    ));
+    let codes: Vec<_> = docs.check(None).iter().map(|d| d.code).collect();
+    assert_eq!(codes, ["missing-cover", "stale-note"]);
+
+    let mut config = project.config().clone();
+    config
+        .check
+        .get_or_insert_with(Default::default)
+        .stale_threshold = Some(1);
+    let docs = Project::builder()
+        .config(config)
+        .root(&dir)
+        .build()
+        .unwrap()
+        .index()
+        .unwrap();
+    let stale = docs.section("Timeouts").unwrap().parts[0]
+        .stale
+        .clone()
+        .unwrap();
+    assert_eq!(stale.paths, ["src/timeout.rs"]);
+}
+
+#[test]
+fn lists_who_knows_what_from_git_and_codeowners() {
+    let dir = docs_dir(
+        "owners",
+        &[
+            (
+                "a.md",
+                "---\ncovers: [src/net]\n---\n## Retries\n\nRetries three times.\n",
+            ),
+            ("b.md", "---\nauthors: [Cy]\n---\n## Setup\n\nRun it.\n"),
+            ("sych.toml", "[owners]\n"),
+            (
+                "CODEOWNERS",
+                "# owners\n* @core\n/src/net/ @net-team\n*.md @writers\n",
+            ),
+        ],
+    );
+    std::fs::create_dir_all(dir.join("src/net")).unwrap();
+    std::fs::write(dir.join("src/net/retry.rs"), "fn retry() {}\n").unwrap();
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "document retries"]);
+    std::fs::write(
+        dir.join("src/net/retry.rs"),
+        "fn retry() {}\nfn backoff() {}\n",
+    )
+    .unwrap();
+    git(
+        &dir,
+        &[
+            "commit",
+            "-q",
+            "-am",
+            "back off",
+            "--author=Bo <bo@example.com>",
+        ],
+    );
+
+    let docs = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap()
+        .index()
+        .unwrap();
+    let section = docs.section("Who knows what").unwrap();
+    assert_eq!(section.parts[0].front_matter.filter, Some(true));
+    let text = docs.text(TextFlavor::Markdown).0;
+    assert!(text.contains(
+        "- **@net-team**: [Retries](/a.md#retries) (owner)\n- **@writers**: [Retries](/a.md#retries) (owner), [Setup](/b.md#setup) (owner)\n- **Ana**: [Retries](/a.md#retries) (notes, code)\n- **Bo**: [Retries](/a.md#retries) (code)\n- **Cy**: [Setup](/b.md#setup) (notes)\n"
+    ));
+    assert!(text.contains(
+        "- [Retries](/a.md#retries): notes by Ana; code by Bo, Ana; owned by @writers, @net-team\n  - `src/net`: code by Bo, Ana; owned by @net-team"
+    ));
+
+    let html = docs.html(false).unwrap();
+    assert!(html.contains(r#"<input class="s-filter""#));
+    assert!(html.contains(r##"<a href="#retries">Retries</a>"##));
+}
+
+#[test]
+fn lists_what_changed_between_revisions() {
+    let dir = docs_dir(
+        "changelog",
+        &[
+            (
+                "a.md",
+                "## Retries\n\nRetries three times.\n\nWaits a second in between.\n\nGives up after that.\n",
+            ),
+            ("b.md", "## Setup\n\nRun it.\n"),
+            ("sych.toml", "[changelog]\nsince = \"v1\"\n"),
+        ],
+    );
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "first notes"]);
+    git(&dir, &["tag", "v1"]);
+    std::fs::write(
+        dir.join("a.md"),
+        "## Retries\n\nRetries five times.\n\nWaits a second in between.\n\nGives up after that.\n",
+    )
+    .unwrap();
+    std::fs::remove_file(dir.join("b.md")).unwrap();
+    std::fs::write(dir.join("c.md"), "## Timeouts\n\nThirty seconds.\n").unwrap();
+
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+    let old = project.index_at("v1").unwrap();
+    assert!(old.section("Setup").is_some());
+    assert!(old.section("Timeouts").is_none());
+
+    let docs = project.index().unwrap();
+    let mut changes: Vec<_> = docs
+        .changes()
+        .iter()
+        .map(|change| (change.title.as_str(), change.kind))
+        .collect();
+    changes.sort();
+    assert_eq!(
+        changes,
+        [
+            ("Retries", ChangeKind::Modified),
+            ("Setup", ChangeKind::Removed),
+            ("Timeouts", ChangeKind::Added),
+        ]
+    );
+    let retries = docs
+        .changes()
+        .iter()
+        .find(|change| change.title == "Retries")
+        .unwrap();
+    assert_eq!(
+        retries.diff,
+        [
+            "-Retries three times.",
+            "+Retries five times.",
+            " Waits a second in between.",
+        ]
+    );
+    let text = docs.text(TextFlavor::Markdown).0;
+    assert!(text.contains("### Added\n\n- [Timeouts](/c.md#timeouts)\n"));
+    assert!(text.contains("- [Retries](/a.md#retries)\n  ```diff\n  -Retries three times.\n"));
+    let feed = docs.atom(false).unwrap();
+    assert!(feed.contains("<title>Modified: Retries</title>"));
+    assert_eq!(feed.matches("<entry>").count(), 3);
+
+    // without `since` the docs are compared with the last build
+    let mut config = project.config().clone();
+    config.changelog.as_mut().unwrap().since = None;
+    let project = Project::builder()
+        .config(config)
+        .root(&dir)
+        .build()
+        .unwrap();
+    let docs = project.index().unwrap();
+    assert!(docs.changes().is_empty());
+    docs.write_snapshot(&dir.join(SYCH_SNAPSHOT)).unwrap();
+    std::fs::remove_file(dir.join("c.md")).unwrap();
+    let docs = project.index().unwrap();
+    assert_eq!(docs.changes()[0].title, "Timeouts");
+    assert_eq!(docs.changes()[0].kind, ChangeKind::Removed);
+
+    assert!(matches!(
+        project.index_at("v9"),
+        Err(Error::Discovery { .. })
+    ));
+}
+
+#[test]
+fn builds_every_tagged_version_with_a_switcher() {
+    let dir = docs_dir(
+        "versions",
+        &[
+            ("a.md", "## Retries\n\nRetries three times.\n"),
+            ("sych.toml", "[versions]\ntags = \"v*\"\n"),
+        ],
+    );
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "first notes"]);
+    git(&dir, &["tag", "v1.9"]);
+    std::fs::write(dir.join("a.md"), "## Retries\n\nRetries five times.\n").unwrap();
+    git(&dir, &["commit", "-q", "-am", "retry more"]);
+    git(&dir, &["tag", "v1.10"]);
+    git(&dir, &["tag", "nightly"]);
+    std::fs::write(dir.join("a.md"), "## Retries\n\nRetries forever.\n").unwrap();
+
+    let project = Project::builder()
+        .config_path(dir.join("sych.toml"))
+        .build()
+        .unwrap();
+    let out = dir.join("out");
+    let labels = project.write_versions(&out, false, false).unwrap();
+    assert_eq!(labels, ["latest", "v1.10", "v1.9"]);
+
+    let read = |path: &str| std::fs::read_to_string(out.join(path)).unwrap();
+    assert!(read("latest/index.html").contains("Retries forever."));
+    assert!(read("v1.10/index.html").contains("Retries five times."));
+    let old = read("v1.9/index.html");
+    assert!(old.contains("Retries three times."));
+    assert!(old.contains(r#"<option value="../v1.9/index.html" selected>v1.9</option>"#));
+    assert!(old.contains(r#"<option value="../latest/index.html" >latest</option>"#));
+    assert!(read("index.html").contains("url=latest/index.html"));
+
+    project.write_versions(&out, true, false).unwrap();
+    assert!(read("v1.10/retries/index.html")
+        .contains(r#"<option value="../../v1.10/index.html" selected>v1.10</option>"#));
+}
+
+#[test]
+fn exports_lists_without_blank_lines_between_items() {
+    let root = docs_dir(
+        "export-lists",
+        &[(
+            "a.md",
+            "## Steps\n\n- Build it:\n  - with cargo\n  - or make\n- Ship it\n",
+        )],
+    );
+    let mut config = SychConfig::default();
+    config.meta.title = "lists".into();
+    let docs = Project::builder()
+        .config(config)
+        .root(&root)
+        .build()
+        .unwrap()
+        .index()
+        .unwrap();
+    let text = docs.text(TextFlavor::Markdown).0;
+    assert!(
+        text.contains("- Build it:\n  - with cargo\n  - or make\n- Ship it\n"),
+        "{text}"
+    );
+
+    // an item which renders to nothing still ends its line
+    let root = docs_dir(
+        "export-empty-items",
+        &[("a.md", "## Steps\n\n- <!-- later -->\n\n- Ship it\n")],
+    );
+    let docs = Project::builder()
+        .config(SychConfig::default())
+        .root(&root)
+        .build()
+        .unwrap()
+        .index()
+        .unwrap();
+    for flavor in [TextFlavor::Markdown, TextFlavor::Text] {
+        let text = docs.text(flavor).0;
+        assert!(text.contains("- \n- Ship it\n"), "{text}");
+    }
+}